@@ -0,0 +1,995 @@
+//! Reading and editing single configuration values by dotted path.
+//!
+//! Edits touch only the text of the changed value, so key order, indentation and comments
+//! are kept. YAML values written in flow style or relying on anchors cannot be edited.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use clap::ArgMatches;
+use serde::Serialize;
+use serde_json::{Value, ser::PrettyFormatter};
+use toml_edit::{DocumentMut, InlineTable, Item, TableLike};
+
+use crate::{
+    config::{
+        format::{ConfigFormat, strip_jsonc},
+        locate::{
+            JsonMember, YamlLine, json_members, skip_whitespace, yaml_comment_start, yaml_key,
+            yaml_lines,
+        },
+        types::LoadOptions,
+        validate::{ValidationError, has_errors, validate_source},
+    },
+    helpers::{
+        cli::{CHECKMARK, primary_text},
+        fs::{load_seyuna_user_config, read_file, resolve_config_path, save_file},
+    },
+};
+
+/// Entrypoint for the `config get` CLI command.
+/// Prints the value at a dotted path, as written in the configuration file or as resolved.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn get(matches: &ArgMatches) -> Result<()> {
+    let config_path = matches.get_one::<PathBuf>("config").map(PathBuf::as_path);
+    let key = matches.get_one::<String>("path").unwrap();
+    let segments = split_path(key)?;
+
+    let (document, source) = if matches.get_flag("resolved") {
+        let loaded = load_seyuna_user_config(&LoadOptions::from_matches(matches)).await?;
+        (
+            serde_json::to_value(&loaded.config)?,
+            "the resolved configuration".to_string(),
+        )
+    } else {
+        let path = resolve_config_path(config_path)?;
+        let content = read_file(&path).await?;
+        (parse_document(&path, &content)?, format!("{:?}", path))
+    };
+
+    let value = value_at(&document, &segments)
+        .with_context(|| format!("{} is not set in {}", key, source))?;
+
+    // Strings are printed bare so they can be used directly in scripts.
+    match value {
+        Value::String(string) => println!("{}", string),
+        value => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+
+    Ok(())
+}
+
+/// Entrypoint for the `config set` CLI command.
+/// Sets the value at a dotted path, creating the enclosing objects when needed.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn set(matches: &ArgMatches) -> Result<()> {
+    let key = matches.get_one::<String>("path").unwrap();
+    let raw = matches.get_one::<String>("value").unwrap();
+
+    let value = if matches.get_flag("string") {
+        Value::String(raw.clone())
+    } else {
+        parse_value(raw)
+    };
+
+    let path = edit_file(matches, key, Some(&value)).await?;
+    println!(
+        "{} {}",
+        *CHECKMARK,
+        primary_text(&format!(
+            "Set {} to {} in {:?}",
+            key,
+            serde_json::to_string(&value)?,
+            path
+        ))
+    );
+
+    Ok(())
+}
+
+/// Entrypoint for the `config unset` CLI command.
+/// Removes the value at a dotted path, so the inherited or default value applies again.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn unset(matches: &ArgMatches) -> Result<()> {
+    let key = matches.get_one::<String>("path").unwrap();
+
+    let path = edit_file(matches, key, None).await?;
+    println!(
+        "{} {}",
+        *CHECKMARK,
+        primary_text(&format!("Removed {} from {:?}", key, path))
+    );
+
+    Ok(())
+}
+
+/// Sets (`Some`) or removes (`None`) the value at `key` in the configuration file, and saves
+/// the file only if the result is still a valid configuration.
+///
+/// # Returns
+/// * `Result<PathBuf>` - The path of the edited file.
+async fn edit_file(matches: &ArgMatches, key: &str, value: Option<&Value>) -> Result<PathBuf> {
+    let path = resolve_config_path(matches.get_one::<PathBuf>("config").map(PathBuf::as_path))?;
+    let segments = split_path(key)?;
+    let content = read_file(&path).await?;
+
+    // The editors rely on the file being well-formed.
+    parse_document(&path, &content)?;
+
+    let updated = edit_content(&path, &content, &segments, value)?;
+
+    // Never save a configuration that would fail to load.
+    let (_, diagnostics) = validate_source(&path, &updated);
+    if has_errors(&diagnostics) {
+        return Err(anyhow!(ValidationError { diagnostics }))
+            .with_context(|| format!("{:?} was not changed", path));
+    }
+
+    save_file(&path, updated.as_bytes()).await?;
+
+    Ok(path)
+}
+
+/// Sets (`Some`) or removes (`None`) the value at `segments` in the content of a
+/// configuration file, in the format of `path`.
+///
+/// # Returns
+/// * `Result<String>` - The edited content.
+pub fn edit_content(
+    path: &Path,
+    content: &str,
+    segments: &[String],
+    value: Option<&Value>,
+) -> Result<String> {
+    match ConfigFormat::from_path(path)? {
+        ConfigFormat::Json => edit_json(content, segments, value),
+        ConfigFormat::Toml => edit_toml(content, segments, value),
+        ConfigFormat::Yaml => edit_yaml(content, segments, value),
+    }
+    .with_context(|| format!("Could not update {} in {:?}", segments.join("."), path))
+}
+
+/// Parses a value given on the command line or in an environment variable.
+/// Values are read as JSON, so `210`, `true` and `{"hue": 210}` keep their type.
+/// Anything that is not valid JSON is taken as a string.
+pub fn parse_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Returns the value at `segments` in a document, where array items are selected by index.
+pub fn value_at<'a>(document: &'a Value, segments: &[String]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(document, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+/// Splits a dotted path such as `ui.theme.colors.brand` into its keys.
+pub fn split_path(key: &str) -> Result<Vec<String>> {
+    let segments: Vec<String> = key.split('.').map(str::to_string).collect();
+    if segments.iter().any(String::is_empty) {
+        return Err(anyhow!(
+            "Invalid path {:?}. Use dot-separated keys, e.g. ui.theme.colors.brand",
+            key
+        ));
+    }
+    Ok(segments)
+}
+
+/// Wraps `value` in one object per key, so that it sits at `segments`.
+pub fn nest_value(segments: &[String], value: Value) -> Value {
+    segments.iter().rev().fold(value, |value, key| {
+        Value::Object([(key.clone(), value)].into_iter().collect())
+    })
+}
+
+/// Parses a configuration file into a generic JSON value, regardless of its format.
+pub fn parse_document(path: &Path, content: &str) -> Result<Value> {
+    ConfigFormat::from_path(path)?
+        .deserialize(content)
+        .map_err(|e| match (e.line, e.column) {
+            (Some(line), Some(column)) => anyhow!("{:?}:{}:{}: {}", path, line, column, e.message),
+            _ => anyhow!("{:?}: {}", path, e.message),
+        })
+}
+
+/// Edits the value at `segments` in the text of a JSON or JSONC file.
+fn edit_json(content: &str, segments: &[String], value: Option<&Value>) -> Result<String> {
+    // Comments and trailing commas are blanked out in place, so offsets stay valid.
+    let stripped = strip_jsonc(content);
+    let bytes = stripped.as_bytes();
+
+    let mut object = skip_whitespace(bytes, 0);
+    for (depth, segment) in segments.iter().enumerate() {
+        if bytes.get(object) != Some(&b'{') {
+            return Err(anyhow!(
+                "{} is not an object",
+                display_path(&segments[..depth])
+            ));
+        }
+        let (members, close) = json_members(bytes, object);
+
+        let Some(index) = members.iter().position(|member| member.key == *segment) else {
+            let Some(value) = value else {
+                return Err(anyhow!("{} is not set", display_path(segments)));
+            };
+            // Wrap the value in the objects that do not exist yet.
+            let nested = nest_value(&segments[depth + 1..], value.clone());
+            return Ok(insert_json_member(
+                content, &members, object, close, segment, &nested,
+            ));
+        };
+
+        let member = &members[index];
+        if depth + 1 < segments.len() {
+            object = member.value_start;
+            continue;
+        }
+
+        return Ok(match value {
+            Some(value) => {
+                let indent = line_indent(content, member.key_start);
+                let text = json_text(value, indent, indent_unit(content));
+                splice(content, member.value_start..member.value_end, &text)
+            }
+            None => remove_json_member(content, bytes, &members, index, object, close),
+        });
+    }
+
+    // The root itself cannot be addressed.
+    Err(anyhow!("A path is required"))
+}
+
+/// Adds a `"key": value` member at the end of the object spanning `open..=close`.
+fn insert_json_member(
+    content: &str,
+    members: &[JsonMember],
+    open: usize,
+    close: usize,
+    key: &str,
+    value: &Value,
+) -> String {
+    let key = serde_json::to_string(key).unwrap();
+    let unit = indent_unit(content);
+
+    match members.last() {
+        // Objects written on a single line stay on a single line.
+        Some(last) if !content[open..last.key_start].contains('\n') => splice(
+            content,
+            last.value_end..last.value_end,
+            &format!(", {}: {}", key, serde_json::to_string(value).unwrap()),
+        ),
+        Some(last) => {
+            let indent = line_indent(content, last.key_start);
+            splice(
+                content,
+                last.value_end..last.value_end,
+                &format!(",\n{}{}: {}", indent, key, json_text(value, indent, unit)),
+            )
+        }
+        None => {
+            let outer = line_indent(content, open);
+            let indent = format!("{}{}", outer, unit);
+            splice(
+                content,
+                open + 1..close,
+                &format!(
+                    "\n{}{}: {}\n{}",
+                    indent,
+                    key,
+                    json_text(value, &indent, unit),
+                    outer
+                ),
+            )
+        }
+    }
+}
+
+/// Removes the member at `index`, along with the comma that separates it from its neighbour.
+/// A member written on lines of its own is removed with those lines, comments included, so
+/// the comments on the lines of its neighbours are kept.
+///
+/// # Arguments
+/// * `content` - The text of the file.
+/// * `bytes` - The text of the file with its comments blanked out.
+/// * `members` - The members of the object spanning `open..=close`.
+/// * `index` - The index of the member to remove.
+fn remove_json_member(
+    content: &str,
+    bytes: &[u8],
+    members: &[JsonMember],
+    index: usize,
+    open: usize,
+    close: usize,
+) -> String {
+    if members.len() == 1 {
+        return splice(content, open + 1..close, "");
+    }
+
+    let member = &members[index];
+    let line_start = content[..member.key_start]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let blank = |range: std::ops::Range<usize>| {
+        bytes[range]
+            .iter()
+            .all(|byte| matches!(byte, b' ' | b'\t' | b'\r'))
+    };
+    // The rest of the last line of the member holds at most its comma and a comment.
+    let mut rest = member.value_end;
+    while bytes
+        .get(rest)
+        .is_some_and(|byte| matches!(byte, b' ' | b'\t'))
+    {
+        rest += 1;
+    }
+    if bytes.get(rest) == Some(&b',') {
+        rest += 1;
+    }
+    let line_end = content[rest..].find('\n').map(|offset| rest + offset);
+
+    match line_end {
+        Some(line_end) if blank(line_start..member.key_start) && blank(rest..line_end) => {
+            let content = splice(content, line_start..line_end + 1, "");
+            if index + 1 < members.len() {
+                return content;
+            }
+            // The previous member becomes the last, and loses its comma.
+            let comma = skip_whitespace(bytes, members[index - 1].value_end);
+            match bytes.get(comma) {
+                Some(b',') => splice(&content, comma..comma + 1, ""),
+                _ => content,
+            }
+        }
+        _ if index == 0 => splice(content, members[0].key_start..members[1].key_start, ""),
+        _ => splice(content, members[index - 1].value_end..member.value_end, ""),
+    }
+}
+
+/// Serializes a JSON value for insertion at a line indented with `indent`.
+fn json_text(value: &Value, indent: &str, unit: &str) -> String {
+    let mut buffer = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        &mut buffer,
+        PrettyFormatter::with_indent(unit.as_bytes()),
+    );
+    value.serialize(&mut serializer).unwrap();
+    String::from_utf8(buffer)
+        .unwrap()
+        .replace('\n', &format!("\n{}", indent))
+}
+
+/// Returns the leading whitespace of the line containing `offset`.
+fn line_indent(content: &str, offset: usize) -> &str {
+    let line_start = content[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line = &content[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Detects the indentation unit of a file from its first indented line, defaulting to two spaces.
+fn indent_unit(content: &str) -> &str {
+    content
+        .lines()
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("  ")
+}
+
+/// Replaces `range` of `content` with `text`.
+fn splice(content: &str, range: std::ops::Range<usize>, text: &str) -> String {
+    let mut result = String::with_capacity(content.len() + text.len());
+    result.push_str(&content[..range.start]);
+    result.push_str(text);
+    result.push_str(&content[range.end..]);
+    result
+}
+
+/// Formats a list of keys as a dotted path.
+fn display_path(segments: &[String]) -> String {
+    if segments.is_empty() {
+        "The configuration".to_string()
+    } else {
+        segments.join(".")
+    }
+}
+
+/// Edits the value at `segments` in a TOML document, keeping its formatting and comments.
+fn edit_toml(content: &str, segments: &[String], value: Option<&Value>) -> Result<String> {
+    let mut document: DocumentMut = content.parse()?;
+    toml_edit_table(document.as_table_mut(), false, segments, segments, value)?;
+    Ok(document.to_string())
+}
+
+/// Edits the value at `rest` within `table`, where `segments` is the full path.
+fn toml_edit_table(
+    table: &mut dyn TableLike,
+    inline: bool,
+    segments: &[String],
+    rest: &[String],
+    value: Option<&Value>,
+) -> Result<()> {
+    let (key, rest) = rest.split_first().unwrap();
+
+    if rest.is_empty() {
+        match value {
+            Some(value) => {
+                let mut new_value = toml_value(value)?;
+                // Keep the comments around a replaced value.
+                if let Some(Item::Value(old_value)) = table.get(key) {
+                    *new_value.decor_mut() = old_value.decor().clone();
+                }
+                table.insert(key, Item::Value(new_value));
+            }
+            None => {
+                table
+                    .remove(key)
+                    .with_context(|| format!("{} is not set", display_path(segments)))?;
+            }
+        }
+        return Ok(());
+    }
+
+    if table.get(key).is_none() {
+        if value.is_none() {
+            return Err(anyhow!("{} is not set", display_path(segments)));
+        }
+        // Tables inside inline tables must be inline as well.
+        let child = if inline {
+            Item::Value(InlineTable::new().into())
+        } else {
+            let mut child = toml_edit::Table::new();
+            child.set_implicit(true);
+            Item::Table(child)
+        };
+        table.insert(key, child);
+    }
+
+    let child = table.get_mut(key).unwrap();
+    let inline = child.is_inline_table();
+    let depth = segments.len() - rest.len();
+    let child = child
+        .as_table_like_mut()
+        .with_context(|| format!("{} is not a table", display_path(&segments[..depth])))?;
+    toml_edit_table(child, inline, segments, rest, value)
+}
+
+/// Converts a JSON value into a TOML value.
+fn toml_value(value: &Value) -> Result<toml_edit::Value> {
+    Ok(match value {
+        Value::Null => {
+            return Err(anyhow!(
+                "TOML has no null value. Use `seyuna config unset` to remove a value."
+            ));
+        }
+        Value::Bool(boolean) => (*boolean).into(),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => integer.into(),
+            None => number
+                .as_f64()
+                .with_context(|| format!("{} cannot be stored in TOML", number))?
+                .into(),
+        },
+        Value::String(string) => string.as_str().into(),
+        Value::Array(items) => items
+            .iter()
+            .map(toml_value)
+            .collect::<Result<toml_edit::Array>>()?
+            .into(),
+        Value::Object(map) => {
+            let mut table = InlineTable::new();
+            for (key, value) in map {
+                table.insert(key, toml_value(value)?);
+            }
+            table.into()
+        }
+    })
+}
+
+/// A `key: value` entry of a YAML block mapping, as line indices and byte offsets.
+struct YamlEntry {
+    key: String,
+    /// The number of leading spaces of the key.
+    indent: usize,
+    /// The index of the line of the key.
+    line: usize,
+    /// The index of the line after the last line of the value.
+    end: usize,
+    /// The offset right after the colon.
+    value_start: usize,
+    /// The value written on the line of the key, without its comment.
+    inline: std::ops::Range<usize>,
+    /// The offset of the comment on the line of the key, if any.
+    comment: Option<usize>,
+}
+
+/// Edits the value at `segments` in the text of a YAML file, keeping its formatting and
+/// comments. Only values nested in block mappings can be edited; anything written in flow
+/// style, e.g. `theme: { mode: dark }`, or relying on anchors is refused.
+fn edit_yaml(content: &str, segments: &[String], value: Option<&Value>) -> Result<String> {
+    // Edit the parsed document first, which reports missing keys and values of the wrong type.
+    let mut expected: serde_yaml::Value = serde_yaml::from_str(content)?;
+    if expected.is_null() {
+        expected = serde_yaml::Mapping::new().into();
+    }
+    set_yaml_value(&mut expected, segments, value)?;
+
+    // Then edit the text, and make sure it changes nothing but the value.
+    let refused = || {
+        anyhow!(
+            "{} cannot be edited without rewriting the YAML file, as it is written in flow \
+            style or uses anchors. Edit the file by hand",
+            display_path(segments)
+        )
+    };
+    let edited = edit_yaml_text(content, segments, value).ok_or_else(refused)?;
+    match serde_yaml::from_str::<serde_yaml::Value>(&edited) {
+        Ok(actual) if actual == expected => Ok(edited),
+        _ => Err(refused()),
+    }
+}
+
+/// Edits the value at `segments` in the parsed YAML document.
+fn set_yaml_value(
+    document: &mut serde_yaml::Value,
+    segments: &[String],
+    value: Option<&Value>,
+) -> Result<()> {
+    let (key, parents) = segments.split_last().unwrap();
+    let mut mapping = document;
+    for (depth, segment) in parents.iter().enumerate() {
+        let map = mapping
+            .as_mapping_mut()
+            .with_context(|| format!("{} is not a mapping", display_path(&segments[..depth])))?;
+        if !map.contains_key(segment.as_str()) {
+            if value.is_none() {
+                return Err(anyhow!("{} is not set", display_path(segments)));
+            }
+            map.insert(segment.as_str().into(), serde_yaml::Mapping::new().into());
+        }
+        mapping = map.get_mut(segment.as_str()).unwrap();
+    }
+
+    let map = mapping
+        .as_mapping_mut()
+        .with_context(|| format!("{} is not a mapping", display_path(parents)))?;
+    match value {
+        Some(value) => {
+            map.insert(key.as_str().into(), serde_yaml::to_value(value)?);
+        }
+        None => {
+            map.shift_remove(key.as_str())
+                .with_context(|| format!("{} is not set", display_path(segments)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Edits the value at `segments` in the text of a YAML file.
+///
+/// # Returns
+/// * `Option<String>` - The edited content; `None` if the file uses syntax that cannot be
+///   edited in place.
+fn edit_yaml_text(content: &str, segments: &[String], value: Option<&Value>) -> Option<String> {
+    let lines = yaml_lines(content)?;
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let unit = indent_unit(content).len();
+
+    let mut range = 0..lines.len();
+    let mut indent = lines
+        .iter()
+        .find(|line| line.content)
+        .map_or(0, |line| line.indent);
+    let mut parent: Option<YamlEntry> = None;
+    for (depth, segment) in segments.iter().enumerate() {
+        let mut entries = yaml_entries(&lines, range.clone(), indent)?;
+
+        let Some(index) = entries.iter().position(|entry| entry.key == *segment) else {
+            // Wrap the value in the mappings that do not exist yet.
+            let nested = nest_value(&segments[depth + 1..], value?.clone());
+            let entry = format!(
+                "{}:{}",
+                yaml_key_text(segment)?,
+                yaml_text(&nested, indent, unit, newline)?
+            );
+            return Some(match (entries.last(), &parent) {
+                (Some(last), _) => {
+                    let offset = line_end(&lines, last.end - 1);
+                    splice(
+                        content,
+                        offset..offset,
+                        &format!("{}{}{}", newline, " ".repeat(indent), entry),
+                    )
+                }
+                (None, Some(parent)) => {
+                    // Add the entry on the line after the key, dropping an empty `{}`.
+                    let offset = line_end(&lines, parent.line);
+                    let content = splice(
+                        content,
+                        offset..offset,
+                        &format!("{}{}{}", newline, " ".repeat(indent), entry),
+                    );
+                    match &content[parent.inline.clone()] {
+                        "{}" => splice(&content, parent.value_start..parent.inline.end, ""),
+                        _ => content,
+                    }
+                }
+                (None, None) => {
+                    let separator = if content.is_empty() || content.ends_with('\n') {
+                        ""
+                    } else {
+                        newline
+                    };
+                    format!("{}{}{}{}", content, separator, entry, newline)
+                }
+            });
+        };
+
+        let entry = entries.swap_remove(index);
+        if depth + 1 < segments.len() {
+            // Only block mappings, possibly empty, can be entered.
+            let inline = &content[entry.inline.clone()];
+            if !inline.is_empty() && inline != "{}" {
+                return None;
+            }
+            range = entry.line + 1..entry.end;
+            indent = lines[range.clone()]
+                .iter()
+                .find(|line| line.content)
+                .map_or(entry.indent + unit, |line| line.indent);
+            parent = Some(entry);
+            continue;
+        }
+
+        return Some(match value {
+            Some(value) => {
+                let comment = entry.comment.map_or(String::new(), |comment| {
+                    format!(" {}", &content[comment..line_end(&lines, entry.line)])
+                });
+                let text = yaml_text(value, indent, unit, newline)?;
+                // Block values go on the lines after the key, scalars stay on its line.
+                let text = match text.strip_prefix(newline) {
+                    Some(_) => format!("{}{}", comment, text),
+                    None => format!("{}{}", text, comment),
+                };
+                splice(
+                    content,
+                    entry.value_start..line_end(&lines, entry.end - 1),
+                    &text,
+                )
+            }
+            None => {
+                let end = lines
+                    .get(entry.end)
+                    .map_or(content.len(), |line| line.start);
+                let content = splice(content, lines[entry.line].start..end, "");
+                // A mapping left without entries is written as `{}`, rather than becoming null.
+                match (entries.is_empty(), &parent) {
+                    (false, _) => content,
+                    (true, Some(parent)) => {
+                        splice(&content, parent.value_start..parent.value_start, " {}")
+                    }
+                    (true, None) => format!("{}{{}}{}", content, newline),
+                }
+            }
+        });
+    }
+
+    None
+}
+
+/// Lists the entries of the block mapping indented with `indent` on the lines of `range`.
+///
+/// # Returns
+/// * `Option<Vec<YamlEntry>>` - The entries; `None` if the lines are not a block mapping.
+fn yaml_entries(
+    lines: &[YamlLine],
+    range: std::ops::Range<usize>,
+    indent: usize,
+) -> Option<Vec<YamlEntry>> {
+    let mut entries: Vec<YamlEntry> = Vec::new();
+    for index in range.clone() {
+        let line = &lines[index];
+        if !line.content || line.indent > indent {
+            continue;
+        }
+        if line.indent < indent {
+            return None;
+        }
+
+        // Sequence items may sit at the indentation of the key they belong to.
+        let text = &line.text[indent..];
+        if text == "-" || text.starts_with("- ") {
+            if entries.is_empty() {
+                return None;
+            }
+            continue;
+        }
+
+        let (key, colon) = yaml_key(text)?;
+        let value_start = line.start + indent + colon;
+        let rest = &text[colon..];
+        let comment = yaml_comment_start(rest);
+        let value = &rest[..comment.unwrap_or(rest.len())];
+        let inline_start = value_start + value.len() - value.trim_start().len();
+        entries.push(YamlEntry {
+            key,
+            indent,
+            line: index,
+            end: index + 1,
+            value_start,
+            inline: inline_start..inline_start + value.trim().len(),
+            comment: comment.map(|comment| value_start + comment),
+        });
+    }
+
+    // Every entry ends with the last line holding a value before the next entry.
+    for index in 0..entries.len() {
+        let next = entries.get(index + 1).map_or(range.end, |entry| entry.line);
+        let entry = &mut entries[index];
+        entry.end = (entry.line + 1..next)
+            .rev()
+            .find(|&line| lines[line].content)
+            .map_or(entry.line + 1, |line| line + 1);
+    }
+    Some(entries)
+}
+
+/// Formats a key, quoting it only when needed.
+fn yaml_key_text(key: &str) -> Option<String> {
+    Some(serde_yaml::to_string(key).ok()?.trim_end().to_string())
+}
+
+/// Serializes a value for insertion after the colon of a key indented with `indent` spaces:
+/// scalars on the line of the key, mappings and sequences on the lines after it.
+fn yaml_text(value: &Value, indent: usize, unit: usize, newline: &str) -> Option<String> {
+    let text = serde_yaml::to_string(value).ok()?;
+    let mut lines = text.trim_end().lines();
+    let block = match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(array) => !array.is_empty(),
+        _ => false,
+    };
+
+    // The lines of multiline strings are already indented below their first line.
+    let (mut result, indent) = if block {
+        (String::new(), indent + unit)
+    } else {
+        (format!(" {}", lines.next()?), indent)
+    };
+    for line in lines {
+        result.push_str(newline);
+        if !line.is_empty() {
+            result.push_str(&" ".repeat(indent));
+            result.push_str(line);
+        }
+    }
+    Some(result)
+}
+
+/// Returns the offset of the end of a line, before its line break.
+fn line_end(lines: &[YamlLine], index: usize) -> usize {
+    lines[index].start + lines[index].text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Sets (`Some`) or removes (`None`) a dotted path in a file of the format of `file`.
+    fn edit(file: &str, content: &str, key: &str, value: Option<Value>) -> String {
+        edit_content(
+            Path::new(file),
+            content,
+            &split_path(key).unwrap(),
+            value.as_ref(),
+        )
+        .unwrap()
+    }
+
+    /// Reads a dotted path from a file of the format of `file`.
+    fn get(file: &str, content: &str, key: &str) -> Option<Value> {
+        let document = parse_document(Path::new(file), content).unwrap();
+        value_at(&document, &split_path(key).unwrap()).cloned()
+    }
+
+    #[test]
+    fn gets_nested_values() {
+        let content = r#"{ "ui": { "theme": { "colors": { "brand": 210 } }, "list": [1, 2] } }"#;
+        assert_eq!(
+            get("seyuna.json", content, "ui.theme.colors.brand"),
+            Some(json!(210))
+        );
+        assert_eq!(get("seyuna.json", content, "ui.list.1"), Some(json!(2)));
+        assert_eq!(get("seyuna.json", content, "ui.theme.missing"), None);
+        assert_eq!(get("seyuna.json", content, "ui.list.2"), None);
+    }
+
+    #[test]
+    fn rejects_empty_keys() {
+        assert!(split_path("ui..mode").is_err());
+        assert!(split_path("").is_err());
+    }
+
+    #[test]
+    fn parses_values_as_json_or_strings() {
+        assert_eq!(parse_value("210"), json!(210));
+        assert_eq!(parse_value("true"), json!(true));
+        assert_eq!(parse_value(r#"{"hue": 210}"#), json!({ "hue": 210 }));
+        assert_eq!(parse_value("dark"), json!("dark"));
+    }
+
+    #[test]
+    fn json_sets_existing_and_nested_values() {
+        let content = "{\n  // The app\n  \"ui\": {\n    \"mode\": \"light\"\n  }\n}\n";
+        assert_eq!(
+            edit("seyuna.jsonc", content, "ui.mode", Some(json!("dark"))),
+            "{\n  // The app\n  \"ui\": {\n    \"mode\": \"dark\"\n  }\n}\n"
+        );
+        assert_eq!(
+            edit(
+                "seyuna.jsonc",
+                content,
+                "ui.theme.colors.brand",
+                Some(json!(210))
+            ),
+            "{\n  // The app\n  \"ui\": {\n    \"mode\": \"light\",\n    \"theme\": {\n      \
+            \"colors\": {\n        \"brand\": 210\n      }\n    }\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn json_unsets_values_and_keeps_comments() {
+        let content = "{\n  \"ui\": {\n    /* dark */ \"mode\": \"dark\",\n    \"name\": \"App\" // name\n  }\n}\n";
+        let updated = edit("seyuna.jsonc", content, "ui.mode", None);
+        assert_eq!(
+            get("seyuna.jsonc", &updated, "ui"),
+            Some(json!({ "name": "App" }))
+        );
+        assert!(updated.contains("// name"));
+
+        let updated = edit("seyuna.jsonc", &updated, "ui.name", None);
+        assert_eq!(get("seyuna.jsonc", &updated, "ui"), Some(json!({})));
+    }
+
+    #[test]
+    fn json_unsets_members_without_the_comments_of_their_neighbours() {
+        let content = "{\n  \"ui\": {\n    \"name\": \"App\", // the name\n    \"mode\": \"dark\", // the mode\n    \"slogan\": \"Hi\" // the slogan\n  }\n}\n";
+        assert_eq!(
+            edit("seyuna.jsonc", content, "ui.mode", None),
+            "{\n  \"ui\": {\n    \"name\": \"App\", // the name\n    \"slogan\": \"Hi\" // the slogan\n  }\n}\n"
+        );
+        assert_eq!(
+            edit("seyuna.jsonc", content, "ui.slogan", None),
+            "{\n  \"ui\": {\n    \"name\": \"App\", // the name\n    \"mode\": \"dark\" // the mode\n  }\n}\n"
+        );
+        assert_eq!(
+            edit("seyuna.jsonc", content, "ui.name", None),
+            "{\n  \"ui\": {\n    \"mode\": \"dark\", // the mode\n    \"slogan\": \"Hi\" // the slogan\n  }\n}\n"
+        );
+
+        // Members sharing a line are cut out of it
+        let content = "{ \"ui\": { \"name\": \"App\", \"mode\": \"dark\" } }\n";
+        assert_eq!(
+            edit("seyuna.json", content, "ui.mode", None),
+            "{ \"ui\": { \"name\": \"App\" } }\n"
+        );
+    }
+
+    #[test]
+    fn toml_sets_and_unsets_values_and_keeps_comments() {
+        let content =
+            "# Seyuna\n[ui]\nmode = \"light\" # default\n\n[ui.theme.colors]\nbrand = 210\n";
+        assert_eq!(
+            edit("seyuna.toml", content, "ui.mode", Some(json!("dark"))),
+            "# Seyuna\n[ui]\nmode = \"dark\" # default\n\n[ui.theme.colors]\nbrand = 210\n"
+        );
+
+        let updated = edit(
+            "seyuna.toml",
+            content,
+            "ui.theme.colors.accent",
+            Some(json!(30)),
+        );
+        assert_eq!(
+            get("seyuna.toml", &updated, "ui.theme.colors"),
+            Some(json!({ "brand": 210, "accent": 30 }))
+        );
+        assert!(updated.starts_with("# Seyuna\n[ui]\nmode = \"light\" # default\n"));
+
+        let updated = edit("seyuna.toml", content, "ui.theme.colors.brand", None);
+        assert_eq!(get("seyuna.toml", &updated, "ui.theme.colors.brand"), None);
+        assert!(updated.contains("# default"));
+    }
+
+    #[test]
+    fn yaml_keeps_the_indentation_and_comments() {
+        let content = "# Seyuna\nui:\n    mode: light # default\n    theme:\n        colors:\n            brand: 210\n";
+        assert_eq!(
+            edit("seyuna.yaml", content, "ui.mode", Some(json!("dark"))),
+            "# Seyuna\nui:\n    mode: dark # default\n    theme:\n        colors:\n            brand: 210\n"
+        );
+        assert_eq!(
+            edit(
+                "seyuna.yaml",
+                content,
+                "ui.theme.colors.accent",
+                Some(json!(30))
+            ),
+            "# Seyuna\nui:\n    mode: light # default\n    theme:\n        colors:\n            brand: 210\n            accent: 30\n"
+        );
+        assert_eq!(
+            edit(
+                "seyuna.yaml",
+                content,
+                "ui.theme.light.chroma",
+                Some(json!(0.1))
+            ),
+            "# Seyuna\nui:\n    mode: light # default\n    theme:\n        colors:\n            brand: 210\n        light:\n            chroma: 0.1\n"
+        );
+    }
+
+    #[test]
+    fn yaml_unsets_values() {
+        let content = "ui:\n  mode: light\n  theme:\n    colors:\n      brand: 210\n";
+        assert_eq!(
+            edit("seyuna.yaml", content, "ui.mode", None),
+            "ui:\n  theme:\n    colors:\n      brand: 210\n"
+        );
+        assert_eq!(
+            edit("seyuna.yaml", content, "ui.theme.colors.brand", None),
+            "ui:\n  mode: light\n  theme:\n    colors: {}\n"
+        );
+    }
+
+    #[test]
+    fn yaml_writes_objects_as_blocks_with_the_line_endings() {
+        let content = "ui:\r\n  mode: light\r\n";
+        assert_eq!(
+            edit(
+                "seyuna.yml",
+                content,
+                "ui.theme.colors.brand",
+                Some(json!({ "hue": 210 }))
+            ),
+            "ui:\r\n  mode: light\r\n  theme:\r\n    colors:\r\n      brand:\r\n        hue: 210\r\n"
+        );
+    }
+
+    #[test]
+    fn yaml_refuses_flow_style() {
+        let content = "ui: { mode: light }\n";
+        assert!(
+            edit_content(
+                Path::new("seyuna.yaml"),
+                content,
+                &split_path("ui.mode").unwrap(),
+                Some(&json!("dark")),
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Configuration inheritance through the `extends` field.
+//!
+//! A configuration file can extend local files (`"./base.json"`) and presets installed in
+//! `node_modules` (`"@acme/seyuna-preset"`). Extended files are merged in order before the
+//! file that extends them, recursively.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::{
+        format::{CONFIG_FILE_EXTENSIONS, CONFIG_FILE_STEM},
+        types::{ConfigLayer, ConfigSource},
+    },
+    helpers::fs::{read_config_file, read_file},
+};
+
+/// One or more configurations to inherit from, as relative paths or package names.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Extends {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Extends {
+    /// Returns the extended configurations in merge order.
+    pub fn specifiers(&self) -> &[String] {
+        match self {
+            Self::One(specifier) => std::slice::from_ref(specifier),
+            Self::Many(specifiers) => specifiers,
+        }
+    }
+}
+
+/// Loads a configuration file and every configuration it extends.
+///
+/// # Arguments
+/// * `path` - The path to the configuration file.
+///
+/// # Returns
+/// `Result<Vec<ConfigLayer>>` - The files of the chain in merge order; `path` comes last.
+pub async fn load_config_chain(path: &Path) -> Result<Vec<ConfigLayer>> {
+    let mut layers = Vec::new();
+    load_layers(path, &mut Vec::new(), &mut layers).await?;
+    Ok(layers)
+}
+
+/// Recursively loads `path` after the files it extends, detecting cycles through `stack`.
+async fn load_layers(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    layers: &mut Vec<ConfigLayer>,
+) -> Result<()> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Could not find the Seyuna configuration file {:?}", path))?;
+
+    // Refuse to extend a file that is already being loaded further up the chain.
+    if stack.contains(&path) {
+        let chain = stack
+            .iter()
+            .chain(std::iter::once(&path))
+            .map(|file| format!("{:?}", file))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        return Err(anyhow::anyhow!("Circular extends detected: {}", chain));
+    }
+
+    let (config, diagnostics) = read_config_file(&path).await?;
+    let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    // Load the extended configurations first so that this file overrides them.
+    stack.push(path.clone());
+    for specifier in config.extends.iter().flat_map(Extends::specifiers) {
+        let extended = resolve_extends(specifier, &directory)
+            .await
+            .with_context(|| format!("Failed to resolve {:?} extended by {:?}", specifier, path))?;
+
+        Box::pin(load_layers(&extended, stack, layers))
+            .await
+            .with_context(|| format!("Failed to load {:?} extended by {:?}", specifier, path))?;
+    }
+    stack.pop();
+
+    layers.push(ConfigLayer {
+        source: ConfigSource::File(path),
+        config,
+        diagnostics,
+    });
+    Ok(())
+}
+
+/// Resolves an `extends` specifier to a configuration file.
+///
+/// Specifiers starting with `.` or `/` are paths relative to `directory`. Anything else is a
+/// package name looked up in the `node_modules` of `directory` and its parents, optionally
+/// followed by a path inside the package (`@acme/seyuna-preset/dark.json`). Without a path, the
+/// package's `package.json` `"seyuna"` field is used, falling back to a `seyuna.*` file in the
+/// package root.
+///
+/// # Arguments
+/// * `specifier` - The value from the `extends` field.
+/// * `directory` - The directory of the file declaring `extends`.
+///
+/// # Returns
+/// `Result<PathBuf>` - The path to the extended configuration file.
+pub async fn resolve_extends(specifier: &str, directory: &Path) -> Result<PathBuf> {
+    if specifier.starts_with('.') || Path::new(specifier).is_absolute() {
+        return Ok(directory.join(specifier));
+    }
+
+    // Split the package name, which is scoped for `@scope/name`, from the path inside it.
+    let segments = if specifier.starts_with('@') { 2 } else { 1 };
+    let mut parts = specifier.splitn(segments + 1, '/');
+    let package_name = parts.by_ref().take(segments).collect::<Vec<_>>().join("/");
+    let subpath = parts.next().filter(|subpath| !subpath.is_empty());
+
+    let package_dir = directory
+        .ancestors()
+        .map(|ancestor| ancestor.join("node_modules").join(&package_name))
+        .find(|candidate| candidate.is_dir())
+        .with_context(|| {
+            format!(
+                "Could not find the package {:?} in node_modules. Is it installed?",
+                package_name
+            )
+        })?;
+
+    if let Some(subpath) = subpath {
+        return Ok(package_dir.join(subpath));
+    }
+
+    // Prefer the configuration declared in the package manifest.
+    let manifest = package_dir.join("package.json");
+    if manifest.is_file() {
+        let content = read_file(&manifest).await?;
+        let manifest_json: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {:?} as JSON", manifest))?;
+        if let Some(entry) = manifest_json.get("seyuna").and_then(Value::as_str) {
+            return Ok(package_dir.join(entry));
+        }
+    }
+
+    CONFIG_FILE_EXTENSIONS
+        .iter()
+        .map(|extension| package_dir.join(format!("{}.{}", CONFIG_FILE_STEM, extension)))
+        .find(|candidate| candidate.is_file())
+        .with_context(|| {
+            format!(
+                "The package {:?} does not contain a Seyuna configuration. \
+                Add a \"seyuna\" field to its package.json or a seyuna.json file.",
+                package_name
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::types::Config, helpers::temp::TempDir, ui::default::UI_CONFIGURATION};
+
+    /// Returns the files of a chain in merge order, relative to the directory.
+    fn files(layers: &[ConfigLayer], directory: &TempDir) -> Vec<String> {
+        layers
+            .iter()
+            .map(|layer| match &layer.source {
+                ConfigSource::File(path) => path
+                    .strip_prefix(directory.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn resolves_paths_relative_to_the_extending_file() {
+        let directory = TempDir::new();
+        directory.write(
+            "presets/base.json",
+            r#"{ "extends": "../shared/colors.json" }"#,
+        );
+        directory.write("shared/colors.json", "{}");
+        let path = directory.write(
+            "app/seyuna.json",
+            r#"{ "extends": ["../presets/base.json", "./local.toml"] }"#,
+        );
+        directory.write("app/local.toml", "");
+
+        let layers = load_config_chain(&path).await.unwrap();
+        assert_eq!(
+            files(&layers, &directory),
+            [
+                "shared/colors.json",
+                "presets/base.json",
+                "app/local.toml",
+                "app/seyuna.json"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resolves_packages_in_node_modules() {
+        let directory = TempDir::new();
+        directory.write(
+            "node_modules/@acme/preset/package.json",
+            r#"{ "seyuna": "theme.json" }"#,
+        );
+        directory.write("node_modules/@acme/preset/theme.json", "{}");
+        directory.write("node_modules/@acme/preset/dark.yaml", "{}");
+        directory.write("node_modules/plain/seyuna.toml", "");
+        let path = directory.write(
+            "apps/web/seyuna.json",
+            r#"{ "extends": ["@acme/preset", "@acme/preset/dark.yaml", "plain"] }"#,
+        );
+
+        let layers = load_config_chain(&path).await.unwrap();
+        assert_eq!(
+            files(&layers, &directory),
+            [
+                "node_modules/@acme/preset/theme.json",
+                "node_modules/@acme/preset/dark.yaml",
+                "node_modules/plain/seyuna.toml",
+                "apps/web/seyuna.json"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn later_files_override_earlier_ones() {
+        let directory = TempDir::new();
+        directory.write(
+            "base.json",
+            r#"{ "ui": { "mode": "dark", "output_dir": "base" } }"#,
+        );
+        directory.write("second.json", r#"{ "ui": { "output_dir": "second" } }"#);
+        let path = directory.write(
+            "seyuna.json",
+            r#"{ "extends": ["./base.json", "./second.json"], "ui": { "mode": "light" } }"#,
+        );
+
+        let layers = load_config_chain(&path).await.unwrap();
+        let config = layers.into_iter().fold(
+            Config {
+                license: None,
+                ui: Some(UI_CONFIGURATION.clone()),
+            },
+            |config, layer| config.merge(layer.config),
+        );
+        let ui = config.ui.unwrap();
+        assert_eq!(ui.mode, crate::ui::types::Mode::Light);
+        assert_eq!(ui.output_dir.as_deref(), Some("second"));
+    }
+
+    #[tokio::test]
+    async fn detects_cycles() {
+        let directory = TempDir::new();
+        directory.write("a.json", r#"{ "extends": "./b.json" }"#);
+        directory.write("b.json", r#"{ "extends": "./a.json" }"#);
+        let path = directory.write("seyuna.json", r#"{ "extends": "./a.json" }"#);
+
+        let error = format!("{:#}", load_config_chain(&path).await.unwrap_err());
+        assert!(error.contains("Circular extends detected"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_packages() {
+        let directory = TempDir::new();
+        let path = directory.write("seyuna.json", r#"{ "extends": "@acme/missing" }"#);
+
+        let error = format!("{:#}", load_config_chain(&path).await.unwrap_err());
+        assert!(error.contains("Is it installed?"), "{}", error);
+    }
+}
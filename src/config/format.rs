@@ -0,0 +1,296 @@
+//! Configuration file formats supported by Seyuna CLI.
+//!
+//! Every format deserializes into the same configuration types, so the rest of the CLI
+//! does not need to know which format a file was written in.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::config::locate::line_column;
+
+/// The file name, without extension, of the Seyuna configuration file.
+pub const CONFIG_FILE_STEM: &str = "seyuna";
+
+/// Recognised configuration file extensions, in the order they are discovered.
+pub const CONFIG_FILE_EXTENSIONS: [&str; 5] = ["json", "jsonc", "toml", "yaml", "yml"];
+
+/// An error from parsing a configuration file, with its 1-based location when known.
+#[derive(Debug)]
+pub struct FormatError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl FormatError {
+    /// Creates an error from a message ending with " at line X column Y", as produced by
+    /// `serde_json` and `serde_yaml`, keeping only the message.
+    fn located(message: String, line: usize, column: usize) -> Self {
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => message,
+        };
+        Self {
+            message,
+            line: Some(line),
+            column: Some(column),
+        }
+    }
+}
+
+/// A configuration file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON, with comments and trailing commas allowed.
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Returns the format matching a file extension, e.g. `"toml"`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" | "jsonc" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Detects the format of a configuration file from its extension.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the configuration file.
+    ///
+    /// # Returns
+    /// `Result<ConfigFormat>` - The detected format.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+            .with_context(|| {
+                format!(
+                    "Unsupported configuration file {:?}. Expected one of the extensions: {}",
+                    path,
+                    CONFIG_FILE_EXTENSIONS.join(", ")
+                )
+            })
+    }
+
+    /// Deserializes a configuration file in this format directly into `T`, keeping the
+    /// location of syntax and type errors.
+    ///
+    /// # Arguments
+    /// * `content` - The raw file content.
+    ///
+    /// # Returns
+    /// `Result<T, FormatError>` - The deserialized value.
+    pub fn deserialize<T: DeserializeOwned>(&self, content: &str) -> Result<T, FormatError> {
+        match self {
+            Self::Json => serde_json::from_str(&strip_jsonc(content))
+                .map_err(|e| FormatError::located(e.to_string(), e.line(), e.column())),
+            Self::Toml => toml::from_str(content).map_err(|e| {
+                let location = e.span().map(|span| line_column(content, span.start));
+                FormatError {
+                    message: e.message().to_string(),
+                    line: location.map(|(line, _)| line),
+                    column: location.map(|(_, column)| column),
+                }
+            }),
+            Self::Yaml => serde_yaml::from_str(content).map_err(|e| match e.location() {
+                Some(location) => {
+                    FormatError::located(e.to_string(), location.line(), location.column())
+                }
+                None => FormatError {
+                    message: e.to_string(),
+                    line: None,
+                    column: None,
+                },
+            }),
+        }
+    }
+
+    /// Serializes a value into a configuration file in this format.
+    ///
+    /// # Arguments
+    /// * `value` - The value to serialize.
+    ///
+    /// # Returns
+    /// `Result<String>` - The serialized file content.
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(value)
+                .map(|json| json + "\n")
+                .context("Failed to serialize JSON"),
+            Self::Toml => toml::to_string_pretty(value).context("Failed to serialize TOML"),
+            Self::Yaml => serde_yaml::to_string(value).context("Failed to serialize YAML"),
+        }
+    }
+}
+
+/// Turns JSON with comments (JSONC) into plain JSON.
+///
+/// Line comments, block comments and trailing commas are replaced with whitespace,
+/// so byte offsets, lines and columns in the result match the original content.
+///
+/// # Arguments
+/// * `content` - The JSONC content.
+///
+/// # Returns
+/// `String` - Plain JSON.
+pub fn strip_jsonc(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    // Blank out a character while keeping its byte length and any line breaks.
+    let blank = |result: &mut String, c: char| {
+        if c == '\n' || c == '\r' {
+            result.push(c);
+        } else {
+            result.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    };
+
+    // Remove comments
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        result.push(escaped);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                result.push(c);
+            }
+            ('/', Some('/')) => {
+                blank(&mut result, c);
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    blank(&mut result, next);
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                blank(&mut result, c);
+                blank(&mut result, chars.next().unwrap_or('*'));
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    blank(&mut result, next);
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    // Remove trailing commas
+    let mut bytes = result.into_bytes();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut pending_comma: Option<usize> = None;
+    for i in 0..bytes.len() {
+        let byte = bytes[i];
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b' ' | b'\t' | b'\n' | b'\r' => {}
+            b',' => pending_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = pending_comma.take() {
+                    bytes[comma] = b' ';
+                }
+            }
+            b'"' => {
+                in_string = true;
+                pending_comma = None;
+            }
+            _ => pending_comma = None,
+        }
+    }
+
+    // Only ASCII bytes were replaced with ASCII spaces, so the content is still valid UTF-8.
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn parse(content: &str) -> Value {
+        serde_json::from_str(&strip_jsonc(content)).unwrap()
+    }
+
+    #[test]
+    fn removes_comments() {
+        let content = "{\n  // line\n  \"a\": 1, /* block\n  comment */ \"b\": 2\n}";
+        assert_eq!(parse(content), json!({ "a": 1, "b": 2 }));
+    }
+
+    #[test]
+    fn keeps_comment_markers_inside_strings() {
+        let content = r#"{ "url": "https://seyuna.dev", "glob": "src/**/*.css" } // end"#;
+        assert_eq!(
+            parse(content),
+            json!({ "url": "https://seyuna.dev", "glob": "src/**/*.css" })
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_quotes_inside_strings() {
+        let content = r#"{ "a": "say \"hi\" // not a comment", "b": "\\" } /* c */"#;
+        assert_eq!(
+            parse(content),
+            json!({ "a": "say \"hi\" // not a comment", "b": "\\" })
+        );
+    }
+
+    #[test]
+    fn removes_nested_trailing_commas() {
+        let content = r#"{ "a": [1, [2, 3,], { "b": [4,], }, ], "c": { "d": {}, }, }"#;
+        assert_eq!(
+            parse(content),
+            json!({ "a": [1, [2, 3], { "b": [4] }], "c": { "d": {} } })
+        );
+    }
+
+    #[test]
+    fn keeps_commas_inside_strings() {
+        assert_eq!(parse(r#"["a,]", "b,}",]"#), json!(["a,]", "b,}"]));
+    }
+
+    #[test]
+    fn keeps_offsets() {
+        let content = "{\n  \"a\": 1, // é\n  /* ü */ \"b\": [2,],\n}";
+        let stripped = strip_jsonc(content);
+        assert_eq!(stripped.len(), content.len());
+        assert_eq!(stripped.lines().count(), content.lines().count());
+        assert_eq!(stripped.find("\"b\""), content.find("\"b\""));
+    }
+}
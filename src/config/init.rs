@@ -1,33 +1,369 @@
+use anyhow::{Context, Result};
 use clap::ArgMatches;
-use std::path::Path;
-use tokio::{fs::File, io::AsyncWriteExt};
+use dialoguer::{Input, Select, theme::ColorfulTheme};
+use indexmap::IndexMap;
+use serde_json::Number;
+use std::{
+    io::{IsTerminal, stdin, stdout},
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    config::types::Config,
-    helpers::cli::{spinner_done, spinner_progress, spinner_start},
-    ui,
+    config::{
+        format::{CONFIG_FILE_STEM, ConfigFormat},
+        migrate::CONFIG_VERSION,
+        patch::MapPatch,
+        types::PartialConfig,
+    },
+    helpers::{
+        cli::{spinner_done, spinner_progress, spinner_start},
+        fs::{find_config_in_dir, save_file},
+    },
+    schema::{schema_reference, write_config_schema},
+    ui::{
+        default::UI_CONFIGURATION,
+        types::{Mode, PartialTheme, PartialUI, ThemeColor},
+    },
 };
 
-pub async fn init(_matches: &ArgMatches) {
-    let spinner = spinner_start("Initializing configuration");
+/// Starter color sets offered by `config init`.
+#[derive(Debug, Clone, Copy)]
+enum Preset {
+    /// The 24 default greek-letter hues, plus the brand hue.
+    Greek,
+    /// Only the brand hue.
+    Brand,
+    /// The brand hue and its complementary accent hue.
+    Complementary,
+}
+
+impl Preset {
+    const ALL: [Preset; 3] = [Preset::Greek, Preset::Brand, Preset::Complementary];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Preset::Greek => "Greek letters (24 hues from alpha to omega, plus brand)",
+            Preset::Brand => "Brand only",
+            Preset::Complementary => "Brand and complementary accent",
+        }
+    }
+
+    /// Returns the `theme.colors` written for this preset.
+    fn colors(&self, brand_hue: u16) -> MapPatch<ThemeColor> {
+        let mut colors = MapPatch {
+            replace: !matches!(self, Preset::Greek),
+            entries: IndexMap::new(),
+        };
+        colors.entries.insert(
+            "brand".to_string(),
+            Some(ThemeColor::Hue(Number::from(brand_hue))),
+        );
+        if let Preset::Complementary = self {
+            colors.entries.insert(
+                "accent".to_string(),
+                Some(ThemeColor::Hue(Number::from((brand_hue + 180) % 360))),
+            );
+        }
+        colors
+    }
+}
+
+/// Answers to the `config init` questions.
+#[derive(Debug)]
+struct Answers {
+    name: String,
+    slogan: String,
+    mode: Mode,
+    output_dir: String,
+    /// The brand hue and starter preset, or `None` to keep the default colors untouched.
+    colors: Option<(u16, Preset)>,
+}
+
+impl Default for Answers {
+    fn default() -> Self {
+        Self {
+            name: UI_CONFIGURATION.name.clone(),
+            slogan: UI_CONFIGURATION.slogan.clone(),
+            mode: UI_CONFIGURATION.mode,
+            output_dir: UI_CONFIGURATION
+                .output_dir
+                .clone()
+                .unwrap_or_else(|| "styles".to_string()),
+            colors: None,
+        }
+    }
+}
+
+impl Answers {
+    /// Asks the user every question, suggesting the defaults.
+    fn prompt() -> Result<Self> {
+        let theme = ColorfulTheme::default();
+        let defaults = Self::default();
+
+        let name = Input::with_theme(&theme)
+            .with_prompt("App name")
+            .default(defaults.name)
+            .interact_text()?;
+        let slogan = Input::with_theme(&theme)
+            .with_prompt("Slogan")
+            .default(defaults.slogan)
+            .interact_text()?;
+
+        let modes = [Mode::System, Mode::Light, Mode::Dark];
+        let mode = Select::with_theme(&theme)
+            .with_prompt("Default color mode")
+            .items(["system", "light", "dark"])
+            .default(
+                modes
+                    .iter()
+                    .position(|mode| *mode == defaults.mode)
+                    .unwrap_or_default(),
+            )
+            .interact()?;
+
+        let output_dir = Input::with_theme(&theme)
+            .with_prompt("Output directory")
+            .default(defaults.output_dir)
+            .interact_text()?;
+
+        let brand_hue: u16 = Input::with_theme(&theme)
+            .with_prompt("Brand hue (0-360)")
+            .default(210)
+            .validate_with(|hue: &u16| {
+                if *hue <= 360 {
+                    Ok(())
+                } else {
+                    Err("The hue must be between 0 and 360")
+                }
+            })
+            .interact_text()?;
+
+        let preset = Select::with_theme(&theme)
+            .with_prompt("Starter colors")
+            .items(Preset::ALL.iter().map(Preset::label))
+            .default(0)
+            .interact()?;
+
+        Ok(Self {
+            name,
+            slogan,
+            mode: modes[mode],
+            output_dir,
+            colors: Some((brand_hue, Preset::ALL[preset])),
+        })
+    }
+
+    /// Builds the configuration file content from the answers.
+    ///
+    /// # Arguments
+    /// * `schema` - The `$schema` reference of the file.
+    fn into_config(self, schema: String) -> PartialConfig {
+        PartialConfig {
+            schema: Some(schema),
+            version: Some(CONFIG_VERSION.to_string()),
+            ui: Some(PartialUI {
+                name: Some(self.name),
+                slogan: Some(self.slogan),
+                theme: self.colors.map(|(brand_hue, preset)| PartialTheme {
+                    colors: Some(preset.colors(brand_hue)),
+                    ..PartialTheme::default()
+                }),
+                mode: Some(self.mode),
+                output_dir: Some(self.output_dir),
+                ..PartialUI::default()
+            }),
+            ..PartialConfig::default()
+        }
+    }
+}
+
+/// Entrypoint for the `config init` CLI command.
+/// Writes a new configuration file, asking for its values when attached to a terminal.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn init(matches: &ArgMatches) -> Result<()> {
+    // The format comes from --format, then from the --config extension, and defaults to JSON.
+    let config_path = matches.get_one::<PathBuf>("config");
+    let extension = matches
+        .get_one::<String>("format")
+        .cloned()
+        .unwrap_or_else(|| {
+            config_path
+                .and_then(|path| path.extension())
+                .and_then(|extension| extension.to_str())
+                .unwrap_or("json")
+                .to_string()
+        });
+    let format = ConfigFormat::from_extension(&extension)
+        .with_context(|| format!("Unsupported configuration format {:?}", extension))?;
+
+    let dest_path = config_path
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", CONFIG_FILE_STEM, extension)));
 
-    let config = Config {
-        license: None,
-        ui: Some(ui::default::UI_CONFIGURATION.clone()),
+    // Never clobber an existing configuration, or hide one in another format, unless asked to.
+    let dest_dir = dest_path.parent().unwrap_or(Path::new(""));
+    if !matches.get_flag("force") {
+        if dest_path.exists() {
+            return Err(anyhow::anyhow!(
+                "{:?} already exists. Use --force to overwrite it.",
+                dest_path
+            ));
+        }
+        if let Some(existing) = find_config_in_dir(dest_dir) {
+            return Err(anyhow::anyhow!(
+                "{:?} already exists, and {:?} would replace it as the configuration of the \
+                directory. Use --force to create it anyway.",
+                existing,
+                dest_path
+            ));
+        }
+    }
+
+    // Ask questions only when a person is there to answer them.
+    let interactive = !matches.get_flag("yes") && stdin().is_terminal() && stdout().is_terminal();
+    let answers = if interactive {
+        Answers::prompt()?
+    } else {
+        Answers::default()
     };
 
-    spinner_progress(&spinner, "Ensuring output directory exists");
-    // let dist_dir = Path::new(config.ui.as_ref().unwrap().output_dir.as_ref().unwrap());
-    // fs::create_dir_all(dist_dir).await.unwrap();
+    let spinner = spinner_start("Initializing configuration");
 
     spinner_progress(&spinner, "Creating configuration file");
-    let json = serde_json::to_string_pretty(&config).unwrap();
+    let content = format.serialize(&answers.into_config(schema_reference()))?;
+
+    spinner_progress(&spinner, &format!("Saving {:?} file", dest_path));
+    save_file(&dest_path, content.as_bytes()).await?;
 
-    spinner_progress(&spinner, "Saving Seyuna.json file");
-    let file_name = "seyuna.json";
-    let dest_path = Path::new(file_name);
-    let mut file = File::create(&dest_path).await.unwrap();
-    file.write_all(json.as_bytes()).await.unwrap();
+    // Write the schema that $schema references, for editor completion.
+    spinner_progress(&spinner, "Writing JSON schema");
+    write_config_schema(&dest_path).await?;
 
     spinner_done(&spinner, "Successfully initialized Seyuna!");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::temp::TempDir;
+    use clap::{Arg, ArgAction, Command, value_parser};
+
+    /// Parses `config init` arguments the way the CLI does.
+    fn matches(args: &[&str]) -> ArgMatches {
+        Command::new("init")
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(Arg::new("format").long("format"))
+            .arg(Arg::new("force").long("force").action(ArgAction::SetTrue))
+            .arg(Arg::new("yes").long("yes").action(ArgAction::SetTrue))
+            .get_matches_from(std::iter::once("init").chain(args.iter().copied()))
+    }
+
+    /// Runs `config init --yes` for a configuration file, with extra arguments.
+    async fn run(config: &Path, args: &[&str]) -> Result<()> {
+        let config = config.to_str().unwrap();
+        init(&matches(&[&["--config", config, "--yes"], args].concat())).await
+    }
+
+    #[tokio::test]
+    async fn writes_the_default_answers_with_yes() {
+        let directory = TempDir::new();
+        let config = directory.path().join("seyuna.json");
+        run(&config, &[]).await.unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config).unwrap()).unwrap();
+        assert_eq!(written["version"], CONFIG_VERSION);
+        assert_eq!(written["ui"]["name"], UI_CONFIGURATION.name.as_str());
+        assert_eq!(written["ui"]["mode"], "system");
+        assert_eq!(written["ui"]["output_dir"], "styles");
+        assert!(written["ui"].get("theme").is_none());
+    }
+
+    #[tokio::test]
+    async fn writes_the_schema_that_schema_references() {
+        let directory = TempDir::new();
+        let config = directory.path().join("seyuna.json");
+        run(&config, &[]).await.unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config).unwrap()).unwrap();
+        let reference = written["$schema"].as_str().unwrap();
+        let schema = std::fs::read_to_string(directory.path().join(reference)).unwrap();
+        assert!(schema.contains("\"properties\""));
+    }
+
+    #[tokio::test]
+    async fn writes_the_requested_format() {
+        let directory = TempDir::new();
+        let config = directory.path().join("seyuna.toml");
+        run(&config, &[]).await.unwrap();
+        let written: toml::Table = std::fs::read_to_string(&config).unwrap().parse().unwrap();
+        assert_eq!(written["version"].as_str(), Some(CONFIG_VERSION));
+
+        // --format wins over the extension of --config
+        let directory = TempDir::new();
+        let config = directory.path().join("config.txt");
+        run(&config, &["--format", "yaml"]).await.unwrap();
+        let written: serde_yaml::Value =
+            serde_yaml::from_str(&std::fs::read_to_string(&config).unwrap()).unwrap();
+        assert_eq!(written["version"].as_str(), Some(CONFIG_VERSION));
+    }
+
+    #[tokio::test]
+    async fn refuses_to_overwrite_an_existing_file() {
+        let directory = TempDir::new();
+        let config = directory.write("seyuna.json", "{ \"ui\": { \"name\": \"Kept\" } }");
+
+        let error = run(&config, &[]).await.unwrap_err();
+        assert!(error.to_string().contains("--force"), "{}", error);
+        assert!(std::fs::read_to_string(&config).unwrap().contains("Kept"));
+    }
+
+    #[tokio::test]
+    async fn refuses_to_hide_a_configuration_in_another_format() {
+        let directory = TempDir::new();
+        directory.write("seyuna.toml", "[ui]\nname = \"Kept\"\n");
+        let config = directory.path().join("seyuna.json");
+
+        let error = run(&config, &[]).await.unwrap_err();
+        assert!(error.to_string().contains("seyuna.toml"), "{}", error);
+        assert!(!config.exists());
+    }
+
+    #[tokio::test]
+    async fn force_overwrites_an_existing_file() {
+        let directory = TempDir::new();
+        let config = directory.write("seyuna.json", "{ \"ui\": { \"name\": \"Replaced\" } }");
+
+        run(&config, &["--force"]).await.unwrap();
+        let written = std::fs::read_to_string(&config).unwrap();
+        assert!(!written.contains("Replaced"));
+        assert!(written.contains(CONFIG_VERSION));
+    }
+
+    #[test]
+    fn presets_write_their_colors() {
+        let colors = |preset: Preset| serde_json::to_value(preset.colors(210)).unwrap();
+
+        assert_eq!(colors(Preset::Greek), serde_json::json!({ "brand": 210 }));
+        assert_eq!(
+            colors(Preset::Brand),
+            serde_json::json!({ "$replace": true, "brand": 210 })
+        );
+        assert_eq!(
+            colors(Preset::Complementary),
+            serde_json::json!({ "$replace": true, "brand": 210, "accent": 30 })
+        );
+    }
 }
@@ -0,0 +1,390 @@
+//! Source locations of configuration values.
+//!
+//! Maps dotted configuration paths such as `ui.theme.colors.alpha` to the byte offset of
+//! their value in the original file, so diagnostics can point at the exact line and column.
+//! The scanners of JSON objects and YAML lines are shared with [`crate::config::edit`], which
+//! edits values in place.
+
+use std::collections::HashMap;
+
+use toml::de::{DeTable, DeValue};
+
+use crate::config::format::{ConfigFormat, strip_jsonc};
+
+/// Byte offsets of every value in a configuration file, keyed by dotted path.
+pub type Positions = HashMap<String, usize>;
+
+/// Finds the byte offset of every value in a configuration file.
+///
+/// Files that cannot be parsed yield the positions found up to the first error.
+///
+/// # Arguments
+/// * `format` - The format of the file.
+/// * `content` - The raw file content.
+///
+/// # Returns
+/// `Positions` - The byte offsets keyed by dotted path.
+pub fn value_positions(format: ConfigFormat, content: &str) -> Positions {
+    let mut positions = Positions::new();
+    match format {
+        ConfigFormat::Json => {
+            let json = strip_jsonc(content);
+            json_positions(json.as_bytes(), 0, &mut Vec::new(), &mut positions);
+        }
+        ConfigFormat::Toml => {
+            let (table, _) = DeTable::parse_recoverable(content);
+            toml_positions(table.get_ref(), &mut Vec::new(), &mut positions);
+        }
+        ConfigFormat::Yaml => yaml_positions(content, &mut positions),
+    }
+    positions
+}
+
+/// Converts a byte offset into a 1-based line and column.
+///
+/// # Arguments
+/// * `content` - The raw file content.
+/// * `offset` - The byte offset.
+///
+/// # Returns
+/// `(usize, usize)` - The line and column, counting columns in characters.
+pub fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(content.len());
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// Records the position of the JSON value starting at `start` and everything nested in it.
+fn json_positions(bytes: &[u8], start: usize, path: &mut Vec<String>, positions: &mut Positions) {
+    let start = skip_whitespace(bytes, start);
+    positions.insert(path.join("."), start);
+
+    match bytes.get(start) {
+        Some(b'{') => {
+            for member in json_members(bytes, start).0 {
+                path.push(member.key);
+                json_positions(bytes, member.value_start, path, positions);
+                path.pop();
+            }
+        }
+        Some(b'[') => {
+            let mut position = start + 1;
+            let mut index = 0;
+            loop {
+                position = skip_whitespace(bytes, position);
+                match bytes.get(position) {
+                    Some(b',') => position += 1,
+                    Some(b']') | None => return,
+                    Some(_) => {
+                        path.push(index.to_string());
+                        json_positions(bytes, position, path, positions);
+                        path.pop();
+                        position = json_value_end(bytes, position);
+                        index += 1;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A `"key": value` member of a JSON object, as byte offsets into the file.
+pub struct JsonMember {
+    pub key: String,
+    pub key_start: usize,
+    pub value_start: usize,
+    pub value_end: usize,
+}
+
+/// Lists the members of the JSON object opening at `open`.
+/// Also returns the offset of the closing brace.
+pub fn json_members(bytes: &[u8], open: usize) -> (Vec<JsonMember>, usize) {
+    let mut members = Vec::new();
+    let mut position = open + 1;
+    loop {
+        position = skip_whitespace(bytes, position);
+        match bytes.get(position) {
+            Some(b'"') => {
+                let key_start = position;
+                let key_end = skip_string(bytes, key_start);
+                let key = serde_json::from_slice(&bytes[key_start..key_end]).unwrap_or_default();
+                // Skip the colon between the key and the value.
+                let value_start = skip_whitespace(bytes, skip_whitespace(bytes, key_end) + 1);
+                let value_end = json_value_end(bytes, value_start);
+                members.push(JsonMember {
+                    key,
+                    key_start,
+                    value_start,
+                    value_end,
+                });
+                position = value_end;
+            }
+            Some(b',') => position += 1,
+            _ => return (members, position),
+        }
+    }
+}
+
+/// Returns the offset right after the JSON value starting at `start`.
+pub fn json_value_end(bytes: &[u8], start: usize) -> usize {
+    let mut position = start;
+    match bytes.get(start) {
+        Some(b'{' | b'[') => {
+            let mut depth = 0;
+            while let Some(byte) = bytes.get(position) {
+                match byte {
+                    b'"' => {
+                        position = skip_string(bytes, position);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return position + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                position += 1;
+            }
+            position
+        }
+        Some(b'"') => skip_string(bytes, start),
+        _ => {
+            while let Some(byte) = bytes.get(position) {
+                if matches!(byte, b',' | b'}' | b']') || byte.is_ascii_whitespace() {
+                    break;
+                }
+                position += 1;
+            }
+            position
+        }
+    }
+}
+
+/// Returns the offset of the next non-whitespace byte.
+pub fn skip_whitespace(bytes: &[u8], mut position: usize) -> usize {
+    while bytes.get(position).is_some_and(u8::is_ascii_whitespace) {
+        position += 1;
+    }
+    position
+}
+
+/// Returns the offset right after the JSON string starting at `start`.
+pub fn skip_string(bytes: &[u8], start: usize) -> usize {
+    let mut position = start + 1;
+    while let Some(byte) = bytes.get(position) {
+        match byte {
+            b'\\' => position += 2,
+            b'"' => return position + 1,
+            _ => position += 1,
+        }
+    }
+    position
+}
+
+/// Records the position of every value in a TOML table.
+fn toml_positions(table: &DeTable, path: &mut Vec<String>, positions: &mut Positions) {
+    for (key, value) in table.iter() {
+        path.push(key.get_ref().to_string());
+        toml_value_positions(value.get_ref(), value.span().start, path, positions);
+        path.pop();
+    }
+}
+
+/// Records the position of a TOML value and everything nested in it.
+fn toml_value_positions(
+    value: &DeValue,
+    start: usize,
+    path: &mut Vec<String>,
+    positions: &mut Positions,
+) {
+    positions.insert(path.join("."), start);
+    match value {
+        DeValue::Table(table) => toml_positions(table, path, positions),
+        DeValue::Array(array) => {
+            for (index, item) in array.iter().enumerate() {
+                path.push(index.to_string());
+                toml_value_positions(item.get_ref(), item.span().start, path, positions);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Records the position of every value in a block-style YAML document.
+///
+/// YAML is located line by line from the indentation of `key: value` pairs and sequence
+/// items, which covers the block style written by `seyuna config --init`. Values in flow
+/// style (`{ ... }`) are located at their parent key.
+fn yaml_positions(content: &str, positions: &mut Positions) {
+    let Some(lines) = yaml_lines(content) else {
+        return;
+    };
+    // Keys of the enclosing mappings with their indentation.
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    // Number of sequence items seen so far, per dotted path of the sequence.
+    let mut sequence_lengths: HashMap<String, usize> = HashMap::new();
+    let dotted = |stack: &[(usize, String)]| {
+        stack
+            .iter()
+            .map(|(_, key)| key.as_str())
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+
+    for line in lines.iter().filter(|line| line.content) {
+        let mut indent = line.indent;
+        while stack.last().is_some_and(|(depth, _)| *depth >= indent) {
+            stack.pop();
+        }
+
+        // Sequence items are keyed by their index.
+        let mut entry = &line.text[indent..];
+        if let Some(item) = entry
+            .strip_prefix("- ")
+            .or(entry.strip_prefix('-').filter(|rest| rest.is_empty()))
+        {
+            let index = sequence_lengths.entry(dotted(&stack)).or_default();
+            stack.push((indent, index.to_string()));
+            *index += 1;
+
+            let item_indent = indent + (entry.len() - item.trim_start().len());
+            positions.insert(dotted(&stack), line.start + item_indent);
+
+            entry = item.trim_start();
+            indent = item_indent;
+        }
+
+        let Some((key, colon)) = yaml_key(entry) else {
+            continue;
+        };
+        stack.push((indent, key));
+
+        let rest = &entry[colon..];
+        let value = &rest[..yaml_comment_start(rest).unwrap_or(rest.len())];
+        let value_offset = if value.trim().is_empty() {
+            line.start + indent
+        } else {
+            line.start + indent + colon + (value.len() - value.trim_start().len())
+        };
+        positions.insert(dotted(&stack), value_offset);
+    }
+}
+
+/// A line of a YAML file.
+pub struct YamlLine<'a> {
+    /// The offset of the line in the file.
+    pub start: usize,
+    /// The line, without its line break.
+    pub text: &'a str,
+    /// The number of leading spaces.
+    pub indent: usize,
+    /// Whether the line holds a value, rather than only whitespace or a comment.
+    pub content: bool,
+}
+
+/// Splits a YAML file into lines.
+///
+/// # Returns
+/// * `Option<Vec<YamlLine>>` - The lines; `None` if the file holds more than one document.
+pub fn yaml_lines(content: &str) -> Option<Vec<YamlLine<'_>>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut seen_content = false;
+    for raw in content.split_inclusive('\n') {
+        let text = raw.trim_end_matches(['\n', '\r']);
+        let trimmed = text.trim();
+        let mut content = !trimmed.is_empty() && !trimmed.starts_with('#');
+
+        // Directives and the start of the document may only precede its content.
+        let marker =
+            text == "---" || text.starts_with("--- ") || text == "..." || text.starts_with('%');
+        if marker {
+            if seen_content {
+                return None;
+            }
+            content = false;
+        }
+        seen_content |= content;
+
+        lines.push(YamlLine {
+            start,
+            text,
+            indent: text.len() - text.trim_start_matches(' ').len(),
+            content,
+        });
+        start += raw.len();
+    }
+    Some(lines)
+}
+
+/// Reads the key of a `key: value` line, without its indentation.
+///
+/// # Returns
+/// * `Option<(String, usize)>` - The key and the offset right after its colon; `None` if the
+///   line is not a simple `key: value` entry.
+pub fn yaml_key(text: &str) -> Option<(String, usize)> {
+    let key_end = match text.chars().next()? {
+        '"' | '\'' => yaml_quoted_end(text)?,
+        '{' | '[' | '&' | '*' | '!' | '?' | '|' | '>' | '%' | '@' | '`' | '#' => return None,
+        _ => text
+            .match_indices(':')
+            .map(|(index, _)| index)
+            .find(|&index| text[index + 1..].is_empty() || text[index + 1..].starts_with(' '))?,
+    };
+
+    let key = &text[..key_end];
+    let key = match key.chars().next()? {
+        '"' | '\'' => serde_yaml::from_str(key).ok()?,
+        _ => key.trim_end().to_string(),
+    };
+    let colon = key_end + text[key_end..].find(':')?;
+    if !text[key_end..colon].trim().is_empty() {
+        return None;
+    }
+    Some((key, colon + 1))
+}
+
+/// Returns the offset right after the quoted string starting `text`.
+fn yaml_quoted_end(text: &str) -> Option<usize> {
+    let quote = text.as_bytes()[0];
+    let bytes = text.as_bytes();
+    let mut position = 1;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'\\' if quote == b'"' => position += 1,
+            // A single quote is escaped by doubling it.
+            b'\'' if quote == b'\'' && bytes.get(position + 1) == Some(&b'\'') => position += 1,
+            byte if byte == quote => return Some(position + 1),
+            _ => {}
+        }
+        position += 1;
+    }
+    None
+}
+
+/// Returns the offset of the comment in the value part of a line, if any.
+pub fn yaml_comment_start(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut position = 0;
+    while position < bytes.len() {
+        let after_space = position == 0 || bytes[position - 1] == b' ';
+        match bytes[position] {
+            b'"' | b'\'' if after_space => {
+                position += yaml_quoted_end(&text[position..]).unwrap_or(bytes.len());
+                continue;
+            }
+            b'#' if after_space => return Some(position),
+            _ => {}
+        }
+        position += 1;
+    }
+    None
+}
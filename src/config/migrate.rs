@@ -0,0 +1,355 @@
+//! Configuration versions and the steps migrating older configurations to the current one.
+//!
+//! A configuration records the version of Seyuna it was written for in `version`, or else in
+//! the `$schema` reference. Every [`Migration`] upgrades configurations written for an older version;
+//! files without a version are assumed to predate every step.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use clap::ArgMatches;
+use owo_colors::OwoColorize;
+use semver::Version;
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+
+use crate::{
+    config::{
+        edit::{edit_content, parse_document},
+        types::PartialConfig,
+        validate::{ValidationError, display_path, has_errors, validate_source},
+    },
+    helpers::{
+        cli::{CHECKMARK, primary_text, secondary_text},
+        fs::{read_file, resolve_config_path, save_file},
+    },
+    schema::{schema_reference, write_config_schema},
+};
+
+/// The configuration version written by this CLI.
+pub const CONFIG_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A change to a configuration document.
+pub struct Change {
+    /// The keys of the changed value.
+    pub path: Vec<String>,
+    /// The new value, or `None` to remove it.
+    pub value: Option<Value>,
+}
+
+/// A step upgrading configurations written for a version before `version`.
+pub struct Migration {
+    /// The version whose configurations no longer need the step.
+    pub version: &'static str,
+    /// What the step changes, shown by `config migrate`.
+    pub description: &'static str,
+    /// Lists the changes the step makes to a document, which may be none.
+    pub changes: fn(&Value) -> Vec<Change>,
+}
+
+/// Every migration step, oldest first.
+/// Add a step whenever a release changes the meaning or the shape of existing values.
+pub static MIGRATIONS: &[Migration] = &[];
+
+/// Returns the version a configuration was written for, from `version` or else `$schema`.
+///
+/// # Arguments
+/// * `version` - The `version` field.
+/// * `schema` - The `$schema` field.
+///
+/// # Returns
+/// `Result<Option<Version>>` - The version, if recorded; an error if `version` is not a
+/// semantic version. A `$schema` reference without a version is not an error.
+pub fn config_version(version: Option<&str>, schema: Option<&str>) -> Result<Option<Version>> {
+    if let Some(version) = version {
+        return Version::parse(version)
+            .map(Some)
+            .with_context(|| format!("version {:?} is not a valid version", version));
+    }
+
+    // e.g. ./schema/v-1.0.0.schema.json
+    Ok(schema
+        .and_then(|url| url.rsplit_once("/v-"))
+        .and_then(|(_, file_name)| file_name.strip_suffix(".schema.json"))
+        .and_then(|version| Version::parse(version).ok()))
+}
+
+/// Returns the migration steps that apply to a configuration written for `version`.
+pub fn pending_migrations(version: Option<&Version>) -> impl Iterator<Item = &'static Migration> {
+    pending_in(MIGRATIONS, version)
+}
+
+/// Returns the steps of `migrations` that apply to a configuration written for `version`.
+fn pending_in<'a>(
+    migrations: &'a [Migration],
+    version: Option<&Version>,
+) -> impl Iterator<Item = &'a Migration> {
+    migrations.iter().filter(move |migration| {
+        version.is_none_or(|version| *version < Version::parse(migration.version).unwrap())
+    })
+}
+
+/// Returns whether migrating a configuration would change any of its values.
+///
+/// # Arguments
+/// * `config` - The configuration, as read from a file.
+/// * `version` - The version it was written for.
+pub fn needs_migration(config: &PartialConfig, version: Option<&Version>) -> bool {
+    let Ok(document) = serde_json::to_value(config) else {
+        return false;
+    };
+    pending_migrations(version).any(|migration| !(migration.changes)(&document).is_empty())
+}
+
+/// Entrypoint for the `config migrate` CLI command.
+/// Upgrades the configuration file to the current version, or shows the changes as a diff
+/// with `--dry-run`.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn migrate(matches: &ArgMatches) -> Result<()> {
+    let path = resolve_config_path(matches.get_one::<PathBuf>("config").map(PathBuf::as_path))?;
+    let content = read_file(&path).await?;
+    let Migrated {
+        content: updated,
+        version,
+        applied,
+        versioned_schema,
+    } = migrate_content(&path, &content, MIGRATIONS)?;
+    let current = Version::parse(CONFIG_VERSION)?;
+
+    if updated == content {
+        println!(
+            "{} {}",
+            *CHECKMARK,
+            primary_text(&format!("{:?} is up to date", path))
+        );
+        return Ok(());
+    }
+
+    // Never save a configuration that would fail to load.
+    let (_, diagnostics) = validate_source(&path, &updated);
+    if has_errors(&diagnostics) {
+        return Err(anyhow!(ValidationError { diagnostics }))
+            .with_context(|| format!("{:?} could not be migrated", path));
+    }
+
+    let from = version.map_or("an unversioned configuration".to_string(), |version| {
+        format!("version {}", version)
+    });
+    if matches.get_flag("dry-run") {
+        print!("{}", diff(&content, &updated));
+        println!("\nWould migrate {:?} from {} to {}", path, from, current);
+    } else {
+        save_file(&path, updated.as_bytes()).await?;
+        println!(
+            "{} {}",
+            *CHECKMARK,
+            primary_text(&format!("Migrated {:?} from {} to {}", path, from, current))
+        );
+    }
+    for migration in applied {
+        println!("  - {}", migration.description);
+    }
+    if versioned_schema && !matches.get_flag("dry-run") {
+        let schema_path = write_config_schema(&path).await?;
+        println!(
+            "{}",
+            secondary_text(&format!(
+                "JSON schema written to {}",
+                display_path(&schema_path)
+            ))
+        );
+    }
+
+    Ok(())
+}
+
+/// A configuration text upgraded by [`migrate_content`].
+struct Migrated<'a> {
+    /// The upgraded text.
+    content: String,
+    /// The version the configuration was written for, if recorded.
+    version: Option<Version>,
+    /// The steps that changed the configuration.
+    applied: Vec<&'a Migration>,
+    /// Whether `$schema` references a versioned schema.
+    versioned_schema: bool,
+}
+
+/// Applies the pending steps of `migrations` to a configuration text and records the current
+/// version in it, keeping its formatting and comments.
+///
+/// # Arguments
+/// * `path` - The path to the configuration file, which determines its format.
+/// * `content` - The text of the configuration file.
+/// * `migrations` - Every migration step, oldest first.
+///
+/// # Returns
+/// `Result<Migrated>` - The upgraded text, or an error if the configuration was written for a
+/// newer version.
+fn migrate_content<'a>(
+    path: &Path,
+    content: &str,
+    migrations: &'a [Migration],
+) -> Result<Migrated<'a>> {
+    let mut document = parse_document(path, content)?;
+
+    let field = |key: &str| document.get(key).and_then(Value::as_str);
+    let version = config_version(field("version"), field("$schema"))
+        .with_context(|| format!("Could not read the version of {:?}", path))?;
+    let versioned_schema = config_version(None, field("$schema")).is_ok_and(|v| v.is_some());
+    let current = Version::parse(CONFIG_VERSION)?;
+    if version.as_ref().is_some_and(|version| *version > current) {
+        return Err(anyhow!(
+            "{:?} was written for Seyuna {}, which is newer than this CLI ({}). \
+            Update the CLI to use it",
+            path,
+            version.unwrap(),
+            current
+        ));
+    }
+
+    // Apply every pending step to the text, so formatting and comments are kept.
+    let mut updated = content.to_string();
+    let mut applied = Vec::new();
+    for migration in pending_in(migrations, version.as_ref()) {
+        let changes = (migration.changes)(&document);
+        if changes.is_empty() {
+            continue;
+        }
+        for change in &changes {
+            updated = edit_content(path, &updated, &change.path, change.value.as_ref())?;
+        }
+        document = parse_document(path, &updated)?;
+        applied.push(migration);
+    }
+
+    // Record the version, and point a versioned $schema at the matching local schema.
+    updated = edit_content(
+        path,
+        &updated,
+        &["version".to_string()],
+        Some(&Value::from(CONFIG_VERSION)),
+    )?;
+    if versioned_schema {
+        updated = edit_content(
+            path,
+            &updated,
+            &["$schema".to_string()],
+            Some(&Value::from(schema_reference())),
+        )?;
+    }
+
+    Ok(Migrated {
+        content: updated,
+        version,
+        applied,
+        versioned_schema,
+    })
+}
+
+/// Formats the line changes between two texts as a colored diff.
+fn diff(old: &str, new: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut output = String::new();
+    for (index, group) in diff.grouped_ops(3).iter().enumerate() {
+        if index > 0 {
+            output.push_str(&format!("{}\n", "...".dimmed()));
+        }
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let line = change.value().trim_end_matches('\n');
+                let line = match change.tag() {
+                    ChangeTag::Delete => format!("{}", format!("-{}", line).red()),
+                    ChangeTag::Insert => format!("{}", format!("+{}", line).green()),
+                    ChangeTag::Equal => format!(" {}", line),
+                };
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moves `ui.outputDir` to `ui.output_dir`.
+    fn rename_output_dir(document: &Value) -> Vec<Change> {
+        let Some(value) = document.pointer("/ui/outputDir") else {
+            return Vec::new();
+        };
+        vec![
+            Change {
+                path: vec!["ui".to_string(), "outputDir".to_string()],
+                value: None,
+            },
+            Change {
+                path: vec!["ui".to_string(), "output_dir".to_string()],
+                value: Some(value.clone()),
+            },
+        ]
+    }
+
+    /// Fails the test if it is ever applied.
+    fn unreachable(_: &Value) -> Vec<Change> {
+        panic!("a step for an older version was applied")
+    }
+
+    static STEPS: &[Migration] = &[
+        Migration {
+            version: "0.1.0",
+            description: "Nothing to do for 0.1.0 configurations",
+            changes: unreachable,
+        },
+        Migration {
+            version: "0.2.0",
+            description: "Rename ui.outputDir to ui.output_dir",
+            changes: rename_output_dir,
+        },
+    ];
+
+    const CONFIG: &str = r#"{
+  "version": "0.1.0",
+  // Where the stylesheet goes
+  "ui": { "outputDir": "css" }
+}
+"#;
+
+    #[test]
+    fn pending_steps_are_applied_and_the_version_recorded() {
+        let migrated = migrate_content(Path::new("seyuna.jsonc"), CONFIG, STEPS).unwrap();
+
+        assert_eq!(migrated.version, Some(Version::new(0, 1, 0)));
+        assert_eq!(migrated.applied.len(), 1);
+        assert_eq!(migrated.applied[0].version, "0.2.0");
+        assert!(migrated.content.contains("// Where the stylesheet goes"));
+
+        let document = parse_document(Path::new("seyuna.jsonc"), &migrated.content).unwrap();
+        assert_eq!(document["version"], CONFIG_VERSION);
+        assert_eq!(document["ui"], serde_json::json!({ "output_dir": "css" }));
+    }
+
+    #[test]
+    fn dry_run_shows_the_changed_lines() {
+        let migrated = migrate_content(Path::new("seyuna.jsonc"), CONFIG, STEPS).unwrap();
+        let output = diff(CONFIG, &migrated.content);
+
+        assert!(output.contains(r#"-  "version": "0.1.0","#));
+        assert!(output.contains(&format!(r#"+  "version": "{}","#, CONFIG_VERSION)));
+        assert!(output.contains(r#"-  "ui": { "outputDir": "css" }"#));
+        assert!(output.contains(" // Where the stylesheet goes"));
+    }
+
+    #[test]
+    fn newer_configurations_are_refused() {
+        let config = r#"{ "version": "999.0.0" }"#;
+        assert!(migrate_content(Path::new("seyuna.json"), config, STEPS).is_err());
+    }
+}
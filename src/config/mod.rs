@@ -1,14 +1,55 @@
 use clap::ArgMatches;
 
-use crate::config::init::init;
+use crate::{
+    config::{
+        edit::{get, set, unset},
+        init::init,
+        migrate::migrate,
+        show::show,
+        validate::validate,
+    },
+    helpers::cli::error_text,
+};
 
+pub mod edit;
+pub mod extends;
+pub mod format;
 pub mod init;
+pub mod locate;
+pub mod migrate;
+pub mod overrides;
+pub mod patch;
+pub mod profile;
+pub mod show;
 pub mod types;
+pub mod validate;
+pub mod workspace;
 
 pub async fn handle_config_args(matches: ArgMatches) {
     if let Some(config_matches) = matches.subcommand_matches("config") {
-        if config_matches.get_flag("init") {
-            init(config_matches).await;
+        match config_matches.subcommand() {
+            Some(("init", init_matches)) => {
+                if let Err(e) = init(init_matches).await {
+                    eprintln!("{}", error_text(&format!("{:#}", e)));
+                    std::process::exit(1); // exit with error
+                }
+            }
+            Some(("validate", validate_matches)) => validate(validate_matches).await,
+            Some((command, command_matches)) => {
+                let result = match command {
+                    "show" => show(command_matches).await,
+                    "get" => get(command_matches).await,
+                    "set" => set(command_matches).await,
+                    "unset" => unset(command_matches).await,
+                    "migrate" => migrate(command_matches).await,
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    eprintln!("{}", error_text(&format!("{:#}", e)));
+                    std::process::exit(1); // exit with error
+                }
+            }
+            None => {}
         }
     }
 }
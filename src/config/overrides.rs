@@ -0,0 +1,273 @@
+//! Overrides of configuration values from environment variables and `--set` flags.
+//!
+//! Every override becomes its own [`ConfigLayer`], merged over the configuration file so
+//! that `config show --resolved --annotate` can tell exactly which variable or flag set a value.
+
+use std::env;
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+
+use crate::config::{
+    edit::{nest_value, parse_value, split_path},
+    types::{ConfigLayer, ConfigSource, PartialConfig},
+    validate::{ValidationError, has_errors, validate_config},
+};
+
+/// The prefix of environment variables overriding configuration values.
+/// Nested keys are separated by a double underscore: `SEYUNA_UI__OUTPUT_DIR=dist/css`.
+/// Keys are lowercased, so keys with uppercase letters, e.g. a color named `brandBlue`, can
+/// only be overridden with `--set`.
+pub const ENV_PREFIX: &str = "SEYUNA_";
+
+/// The top-level keys whose values environment variables can override.
+/// Other `SEYUNA_*` variables, e.g. `SEYUNA_PROFILE` or `SEYUNA_LICENSE`, are left alone.
+const ENV_KEYS: &[&str] = &["ui"];
+
+/// Builds one layer per `SEYUNA_<KEY>__*` environment variable, sorted by name, where `KEY`
+/// is one of [`ENV_KEYS`].
+///
+/// # Returns
+/// `Result<Vec<ConfigLayer>>` - The environment variable overrides.
+pub fn env_layers() -> Result<Vec<ConfigLayer>> {
+    variable_layers(env::vars())
+}
+
+/// Builds the layers of [`env_layers`] from a set of environment variables.
+pub fn variable_layers(
+    variables: impl Iterator<Item = (String, String)>,
+) -> Result<Vec<ConfigLayer>> {
+    let mut variables: Vec<(String, String)> = variables
+        .filter(|(name, _)| {
+            name.strip_prefix(ENV_PREFIX)
+                .and_then(|name| name.split_once("__"))
+                .is_some_and(|(key, _)| {
+                    ENV_KEYS
+                        .iter()
+                        .any(|env_key| key.eq_ignore_ascii_case(env_key))
+                })
+        })
+        .collect();
+    variables.sort();
+
+    variables
+        .into_iter()
+        .map(|(name, raw)| {
+            // Keys are lowercase, and the variable names are conventionally uppercase.
+            let segments: Vec<String> = name[ENV_PREFIX.len()..]
+                .split("__")
+                .map(str::to_ascii_lowercase)
+                .collect();
+            if segments.iter().any(String::is_empty) {
+                return Err(anyhow!(
+                    "Invalid environment variable {}. Separate nested keys with `__`, \
+                    e.g. SEYUNA_UI__OUTPUT_DIR",
+                    name
+                ));
+            }
+            override_layer(ConfigSource::Env(name), &segments, &raw)
+        })
+        .collect()
+}
+
+/// Builds one layer per `--set path=value` assignment, in command-line order.
+///
+/// # Arguments
+/// * `assignments` - The values of the `--set` flags.
+///
+/// # Returns
+/// `Result<Vec<ConfigLayer>>` - The command-line overrides.
+pub fn cli_layers(assignments: &[String]) -> Result<Vec<ConfigLayer>> {
+    assignments
+        .iter()
+        .map(|assignment| {
+            let (path, raw) = assignment.split_once('=').with_context(|| {
+                format!(
+                    "Invalid --set {:?}. Expected PATH=VALUE, e.g. --set ui.mode=dark",
+                    assignment
+                )
+            })?;
+            let segments = split_path(path.trim())?;
+            override_layer(ConfigSource::Cli(segments.join(".")), &segments, raw)
+        })
+        .collect()
+}
+
+/// Parses and validates a single override.
+/// A number, a boolean or `null` that does not fit the field is taken as the string it was
+/// written as, so `SEYUNA_UI__NAME=2024` sets the name to `"2024"`. Arrays and objects must
+/// fit their field.
+fn override_layer(source: ConfigSource, segments: &[String], raw: &str) -> Result<ConfigLayer> {
+    let deserialize = |value: Value| -> serde_json::Result<PartialConfig> {
+        serde_json::from_value(nest_value(segments, value))
+    };
+    let value = parse_value(raw);
+    let scalar = !matches!(value, Value::Array(_) | Value::Object(_));
+    let config = deserialize(value)
+        .or_else(|e| {
+            if scalar {
+                deserialize(Value::String(raw.to_string())).map_err(|_| e)
+            } else {
+                Err(e)
+            }
+        })
+        .with_context(|| format!("Invalid configuration override {}={}", source, raw))?;
+
+    let diagnostics = validate_config(source.clone(), &config);
+    if has_errors(&diagnostics) {
+        return Err(ValidationError { diagnostics }.into());
+    }
+
+    Ok(ConfigLayer {
+        source,
+        config,
+        diagnostics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Returns the override of every layer as JSON.
+    fn values(layers: &[ConfigLayer]) -> Vec<Value> {
+        layers
+            .iter()
+            .map(|layer| serde_json::to_value(&layer.config).unwrap())
+            .collect()
+    }
+
+    fn variables(variables: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn reads_nested_keys_from_environment_variables() {
+        let layers = variable_layers(variables(&[
+            ("SEYUNA_UI__THEME__LIGHT__CHROMA", "0.1"),
+            ("SEYUNA_UI__MODE", "dark"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            values(&layers),
+            [
+                json!({ "ui": { "mode": "dark" } }),
+                json!({ "ui": { "theme": { "light": { "chroma": 0.1 } } } }),
+            ]
+        );
+        assert!(matches!(&layers[0].source, ConfigSource::Env(name) if name == "SEYUNA_UI__MODE"));
+    }
+
+    #[test]
+    fn ignores_other_variables() {
+        let layers = variable_layers(variables(&[
+            ("SEYUNA_PROFILE", "production"),
+            ("SEYUNA_TELEMETRY", "0"),
+            ("SEYUNA_LICENSE_PUBLIC_KEY", "key"),
+            ("SEYUNA_OTHER__KEY", "value"),
+            ("PATH", "/usr/bin"),
+        ]))
+        .unwrap();
+        assert!(layers.is_empty());
+    }
+
+    #[test]
+    fn rejects_empty_keys() {
+        assert!(variable_layers(variables(&[("SEYUNA_UI____MODE", "dark")])).is_err());
+    }
+
+    #[test]
+    fn keeps_strings_that_look_like_other_types() {
+        let layers = variable_layers(variables(&[
+            ("SEYUNA_UI__NAME", "2024"),
+            ("SEYUNA_UI__OUTPUT_DIR", "5"),
+            ("SEYUNA_UI__SLOGAN", "true"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            values(&layers),
+            [
+                json!({ "ui": { "name": "2024" } }),
+                json!({ "ui": { "output_dir": "5" } }),
+                json!({ "ui": { "slogan": "true" } }),
+            ]
+        );
+    }
+
+    #[test]
+    fn lowercases_the_keys_of_environment_variables() {
+        let layers =
+            variable_layers(variables(&[("SEYUNA_UI__THEME__COLORS__BRANDBLUE", "30")])).unwrap();
+        assert_eq!(
+            values(&layers),
+            [json!({ "ui": { "theme": { "colors": { "brandblue": 30 } } } })]
+        );
+
+        // Keys with uppercase letters are set with --set instead
+        let layers = cli_layers(&["ui.theme.colors.brandBlue=30".to_string()]).unwrap();
+        assert_eq!(
+            values(&layers),
+            [json!({ "ui": { "theme": { "colors": { "brandBlue": 30 } } } })]
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_do_not_fit_their_field() {
+        let error =
+            |assignment: &str| format!("{:#}", cli_layers(&[assignment.to_string()]).unwrap_err());
+        assert_eq!(
+            error("ui.name=[1, 2]"),
+            "Invalid configuration override --set ui.name=[1, 2]: \
+            invalid type: sequence, expected a string"
+        );
+        assert!(
+            error(r#"ui.theme.semantic.primary={"light": 1}"#).starts_with(
+                r#"Invalid configuration override --set ui.theme.semantic.primary={"light": 1}: "#
+            )
+        );
+        assert_eq!(
+            error("ui.theme.light.chroma=abc"),
+            "Invalid configuration override --set ui.theme.light.chroma=abc: \
+            invalid type: string \"abc\", expected a JSON number"
+        );
+
+        let error = variable_layers(variables(&[("SEYUNA_UI__BUILD__MINIFY", "yes")])).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Invalid configuration override SEYUNA_UI__BUILD__MINIFY=yes: \
+            invalid type: string \"yes\", expected a boolean"
+        );
+    }
+
+    #[test]
+    fn reads_set_flags_in_order() {
+        let layers = cli_layers(&[
+            "ui.mode=dark".to_string(),
+            "ui.theme.colors.brand=210".to_string(),
+            "ui.name=2024".to_string(),
+            "ui.theme.colors.accent={\"hue\": 30}".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            values(&layers),
+            [
+                json!({ "ui": { "mode": "dark" } }),
+                json!({ "ui": { "theme": { "colors": { "brand": 210 } } } }),
+                json!({ "ui": { "name": "2024" } }),
+                json!({ "ui": { "theme": { "colors": { "accent": { "hue": 30 } } } } }),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_set_flags() {
+        assert!(cli_layers(&["ui.mode".to_string()]).is_err());
+        assert!(cli_layers(&["ui..mode=dark".to_string()]).is_err());
+        assert!(cli_layers(&["ui.mode=sepia".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Merge strategies for map-valued configuration.
+//!
+//! Maps in a user configuration extend the map they are merged over by default.
+//! A [`MapPatch`] additionally lets the user drop inherited entries or replace the
+//! inherited map altogether.
+
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+use indexmap::IndexMap;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+};
+
+/// Key that, when set to `true`, makes a map replace the inherited map instead of extending it.
+pub const REPLACE_KEY: &str = "$replace";
+
+/// A user-provided map that is merged over an inherited map.
+///
+/// Entries are inserted into, or overwrite entries of, the inherited map. An entry set to
+/// `null` removes the inherited entry, and `"$replace": true` discards every inherited entry
+/// before the remaining entries are applied.
+#[derive(Debug, Clone)]
+pub struct MapPatch<T> {
+    pub replace: bool,
+    pub entries: IndexMap<String, Option<T>>,
+}
+
+impl<T> Default for MapPatch<T> {
+    fn default() -> Self {
+        Self {
+            replace: false,
+            entries: IndexMap::new(),
+        }
+    }
+}
+
+impl<T> MapPatch<T> {
+    /// Applies the patch to an inherited map.
+    ///
+    /// # Arguments
+    /// * `base` - The inherited map.
+    ///
+    /// # Returns
+    /// `IndexMap<String, T>` - The merged map. Inherited entries keep their position.
+    pub fn apply(self, base: IndexMap<String, T>) -> IndexMap<String, T> {
+        let mut merged = if self.replace { IndexMap::new() } else { base };
+
+        for (key, value) in self.entries {
+            match value {
+                Some(value) => {
+                    merged.insert(key, value);
+                }
+                None => {
+                    merged.shift_remove(&key);
+                }
+            }
+        }
+
+        merged
+    }
+}
+
+/// Deserializes an optional field that can also be cleared: a missing field is `None`, keeping
+/// the inherited value, while `null` is `Some(None)`, clearing it like a [`MapPatch`] entry.
+///
+/// Use with `#[serde(default, deserialize_with = "nullable")]` on an `Option<Option<T>>`.
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl<T: Serialize> Serialize for MapPatch<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        if self.replace {
+            map.serialize_entry(REPLACE_KEY, &true)?;
+        }
+        for (key, value) in &self.entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for MapPatch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapPatchVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for MapPatchVisitor<T> {
+            type Value = MapPatch<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut patch = MapPatch::default();
+                while let Some(key) = access.next_key::<String>()? {
+                    if key == REPLACE_KEY {
+                        patch.replace = access.next_value()?;
+                    } else {
+                        let value = access.next_value()?;
+                        patch.entries.insert(key, value);
+                    }
+                }
+                Ok(patch)
+            }
+        }
+
+        deserializer.deserialize_map(MapPatchVisitor(PhantomData))
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for MapPatch<T> {
+    fn schema_name() -> Cow<'static, str> {
+        format!("MapPatch_of_{}", T::schema_name()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                REPLACE_KEY: {
+                    "description": "Discard every inherited entry instead of extending the inherited map.",
+                    "type": "boolean",
+                },
+            },
+            "additionalProperties": generator.subschema_for::<Option<T>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn base() -> IndexMap<String, u8> {
+        IndexMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+    }
+
+    fn patch(value: serde_json::Value) -> MapPatch<u8> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn extends_the_inherited_map() {
+        let merged = patch(json!({ "b": 20, "c": 3 })).apply(base());
+        assert_eq!(
+            merged.into_iter().collect::<Vec<_>>(),
+            [
+                ("a".to_string(), 1),
+                ("b".to_string(), 20),
+                ("c".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn null_removes_inherited_entries() {
+        let merged = patch(json!({ "a": null, "missing": null })).apply(base());
+        assert_eq!(merged, IndexMap::from([("b".to_string(), 2)]));
+    }
+
+    #[test]
+    fn replace_discards_inherited_entries() {
+        let merged = patch(json!({ "$replace": true, "c": 3 })).apply(base());
+        assert_eq!(merged, IndexMap::from([("c".to_string(), 3)]));
+
+        let merged = patch(json!({ "$replace": false, "c": 3 })).apply(base());
+        assert_eq!(merged.len(), 3);
+    }
+
+    #[test]
+    fn replace_and_null_round_trip() {
+        let value = json!({ "$replace": true, "a": null, "c": 3 });
+        assert_eq!(serde_json::to_value(patch(value.clone())).unwrap(), value);
+    }
+}
@@ -0,0 +1,272 @@
+//! Named configuration profiles.
+//!
+//! A configuration file can declare `profiles`, partial configurations merged over it when
+//! selected, so one file can describe both development and production builds:
+//!
+//! ```json
+//! {
+//!   "ui": { "build": { "minify": false, "source_map": true } },
+//!   "profiles": {
+//!     "production": { "ui": { "build": { "minify": true, "source_map": false, "hash": true } } }
+//!   }
+//! }
+//! ```
+
+use std::env;
+
+use anyhow::{Result, anyhow};
+use indexmap::IndexSet;
+
+use crate::config::types::{ConfigLayer, ConfigSource};
+
+/// The environment variable selecting a profile when `--profile` is not given.
+pub const PROFILE_ENV: &str = "SEYUNA_PROFILE";
+
+/// Returns the selected profile: the `--profile` flag, or else [`PROFILE_ENV`].
+///
+/// # Arguments
+/// * `flag` - The value of `--profile`, if given.
+///
+/// # Returns
+/// `Option<String>` - The profile name, or `None` if none is selected or the name is empty.
+pub fn selected_profile(flag: Option<&String>) -> Option<String> {
+    select_profile(flag, env::var(PROFILE_ENV).ok())
+}
+
+/// Selects the profile of [`selected_profile`] from the flag and the environment variable.
+fn select_profile(flag: Option<&String>, variable: Option<String>) -> Option<String> {
+    flag.cloned()
+        .or(variable)
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Builds the layers of the profile `name` from every file of the chain that declares it,
+/// in merge order, so a profile of the user's file overrides the same profile of a preset.
+///
+/// # Arguments
+/// * `layers` - The files of the `extends` chain.
+/// * `name` - The selected profile.
+/// * `required` - Whether a file must declare the profile. Workspace roots and members do not
+///   have to, as the profile is selected for the whole workspace.
+///
+/// # Returns
+/// `Result<Vec<ConfigLayer>>` - The profile layers, or an error if the profile is required and
+/// no file declares it.
+pub fn profile_layers(
+    layers: &[ConfigLayer],
+    name: &str,
+    required: bool,
+) -> Result<Vec<ConfigLayer>> {
+    let selected: Vec<ConfigLayer> = layers
+        .iter()
+        .filter_map(|layer| {
+            let file = layer.source.path()?;
+            let profile = layer.config.profiles.as_ref()?.get(name)?;
+            Some(ConfigLayer {
+                source: ConfigSource::Profile {
+                    name: name.to_string(),
+                    file: file.to_path_buf(),
+                },
+                config: profile.clone(),
+                // The profile was validated along with its file.
+                diagnostics: Vec::new(),
+            })
+        })
+        .collect();
+
+    if selected.is_empty() && required {
+        let available: IndexSet<&String> = layers
+            .iter()
+            .filter_map(|layer| layer.config.profiles.as_ref())
+            .flat_map(|profiles| profiles.keys())
+            .collect();
+        return Err(anyhow!(
+            "Unknown profile {:?}. {}",
+            name,
+            if available.is_empty() {
+                "The configuration does not declare any profiles.".to_string()
+            } else {
+                format!(
+                    "Available profiles: {}",
+                    available
+                        .iter()
+                        .map(|name| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+        ));
+    }
+
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{
+            extends::load_config_chain,
+            overrides::{cli_layers, variable_layers},
+            types::{Config, LoadOptions},
+        },
+        helpers::{fs::load_seyuna_user_config, temp::TempDir},
+        ui::default::UI_CONFIGURATION,
+    };
+    use serde_json::{Value, json};
+
+    const CONFIG: &str = r#"{
+        "ui": { "name": "Base", "build": { "minify": false, "source_map": true } },
+        "profiles": {
+            "production": { "ui": { "name": "Production", "build": { "minify": true } } },
+            "staging": { "ui": { "build": { "source_map": false } } }
+        }
+    }"#;
+
+    /// Merges the layers over the defaults and returns the name and build settings.
+    fn resolve(layers: &[ConfigLayer]) -> Value {
+        let default_configuration = Config {
+            license: None,
+            ui: Some(UI_CONFIGURATION.clone()),
+        };
+        let config = layers.iter().fold(default_configuration, |config, layer| {
+            config.merge(layer.config.clone())
+        });
+        let ui = config.ui.unwrap();
+        json!({
+            "name": ui.name,
+            "minify": ui.build.minify,
+            "source_map": ui.build.source_map,
+        })
+    }
+
+    #[test]
+    fn the_flag_wins_over_the_environment_variable() {
+        let flag = "production".to_string();
+        let variable = || Some("staging".to_string());
+        assert_eq!(select_profile(Some(&flag), variable()), Some(flag.clone()));
+        assert_eq!(select_profile(None, variable()), variable());
+        assert_eq!(select_profile(None, None), None);
+        // An empty name selects no profile
+        assert_eq!(select_profile(None, Some(String::new())), None);
+        assert_eq!(select_profile(Some(&String::new()), variable()), None);
+    }
+
+    #[tokio::test]
+    async fn profiles_override_the_configuration_file() {
+        let directory = TempDir::new();
+        let path = directory.write("seyuna.json", CONFIG);
+        let mut layers = load_config_chain(&path).await.unwrap();
+        assert_eq!(
+            resolve(&layers),
+            json!({ "name": "Base", "minify": false, "source_map": true })
+        );
+
+        let profile = profile_layers(&layers, "production", true).unwrap();
+        assert_eq!(
+            profile[0].source,
+            ConfigSource::Profile {
+                name: "production".to_string(),
+                file: path,
+            }
+        );
+        layers.extend(profile);
+        assert_eq!(
+            resolve(&layers),
+            json!({ "name": "Production", "minify": true, "source_map": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn profiles_of_the_user_file_override_those_of_presets() {
+        let directory = TempDir::new();
+        directory.write("preset.json", CONFIG);
+        let path = directory.write(
+            "seyuna.json",
+            r#"{
+                "extends": "./preset.json",
+                "profiles": { "production": { "ui": { "name": "Shop" } } }
+            }"#,
+        );
+        let mut layers = load_config_chain(&path).await.unwrap();
+        let profile = profile_layers(&layers, "production", true).unwrap();
+        assert_eq!(profile.len(), 2);
+        layers.extend(profile);
+        assert_eq!(
+            resolve(&layers),
+            json!({ "name": "Shop", "minify": true, "source_map": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn environment_variables_and_set_flags_override_profiles() {
+        let directory = TempDir::new();
+        let path = directory.write("seyuna.json", CONFIG);
+        let mut layers = load_config_chain(&path).await.unwrap();
+        layers.extend(profile_layers(&layers, "production", true).unwrap());
+        layers.extend(
+            variable_layers(
+                [("SEYUNA_UI__BUILD__MINIFY".to_string(), "false".to_string())].into_iter(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            resolve(&layers),
+            json!({ "name": "Production", "minify": false, "source_map": true })
+        );
+
+        layers.extend(cli_layers(&["ui.name=Preview".to_string()]).unwrap());
+        assert_eq!(
+            resolve(&layers),
+            json!({ "name": "Preview", "minify": false, "source_map": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn the_selected_profile_is_loaded() {
+        let directory = TempDir::new();
+        let options = LoadOptions {
+            config_path: Some(directory.write("seyuna.json", CONFIG)),
+            profile: Some("staging".to_string()),
+            overrides: vec!["ui.build.minify=true".to_string()],
+            ..LoadOptions::default()
+        };
+        let loaded = load_seyuna_user_config(&options).await.unwrap();
+        assert_eq!(
+            resolve(&loaded.layers),
+            json!({ "name": "Base", "minify": true, "source_map": false })
+        );
+
+        let options = LoadOptions {
+            profile: Some("preview".to_string()),
+            ..options
+        };
+        assert!(load_seyuna_user_config(&options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unknown_profiles_are_errors() {
+        let directory = TempDir::new();
+        let path = directory.write("seyuna.json", CONFIG);
+        let layers = load_config_chain(&path).await.unwrap();
+        let error = profile_layers(&layers, "preview", true).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"Unknown profile "preview". Available profiles: production, staging"#
+        );
+        // Workspace roots and members may leave the profile out
+        assert!(
+            profile_layers(&layers, "preview", false)
+                .unwrap()
+                .is_empty()
+        );
+
+        let path = directory.write("other/seyuna.json", "{}");
+        let layers = load_config_chain(&path).await.unwrap();
+        let error = profile_layers(&layers, "preview", true).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"Unknown profile "preview". The configuration does not declare any profiles."#
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! Printing the configuration, optionally annotated with where each value comes from.
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use indexmap::IndexMap;
+use owo_colors::OwoColorize;
+use serde_json::{Value, json};
+use toml_edit::DocumentMut;
+
+use crate::{
+    config::{
+        format::ConfigFormat,
+        types::{ConfigLayer, ConfigSource, LoadOptions},
+    },
+    helpers::fs::load_seyuna_user_config,
+};
+
+/// Entrypoint for the `config show` CLI command.
+/// Prints the user's configuration file, or with `--resolved` the effective configuration
+/// after every layer has been merged over the defaults.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn show(matches: &ArgMatches) -> Result<()> {
+    let loaded = load_seyuna_user_config(&LoadOptions::from_matches(matches)).await?;
+
+    let (document, layers) = if matches.get_flag("resolved") {
+        (serde_json::to_value(&loaded.config)?, &loaded.layers[..])
+    } else {
+        // Extended files are merged first, so the user's file is the last file layer.
+        let file = loaded
+            .layers
+            .iter()
+            .rposition(|layer| layer.source.path().is_some())
+            .context("The configuration file is not part of the loaded configuration")?;
+        (
+            serde_json::to_value(&loaded.layers[file].config)?,
+            &loaded.layers[file..=file],
+        )
+    };
+
+    let annotate = matches.get_flag("annotate");
+    let sources = value_sources(&document, layers)?;
+    let format = matches.get_one::<String>("format").map(String::as_str);
+    let output = format_output(&document, &sources, format, annotate)?;
+    print!("{}", output);
+
+    Ok(())
+}
+
+/// Formats a configuration in the requested format.
+///
+/// # Arguments
+/// * `document` - The configuration to print.
+/// * `sources` - The source of every value of `document`.
+/// * `format` - `json`, `toml`, or `None` for a table.
+/// * `annotate` - Whether to print the source of every value.
+///
+/// # Returns
+/// `Result<String>` - The formatted configuration.
+fn format_output(
+    document: &Value,
+    sources: &IndexMap<String, ConfigSource>,
+    format: Option<&str>,
+    annotate: bool,
+) -> Result<String> {
+    Ok(match format {
+        Some("json") if annotate => {
+            let sources: IndexMap<&String, String> = sources
+                .iter()
+                .map(|(path, source)| (path, source.to_string()))
+                .collect();
+            ConfigFormat::Json.serialize(&json!({ "config": document, "sources": sources }))?
+        }
+        Some("json") => ConfigFormat::Json.serialize(document)?,
+        Some("toml") if annotate => annotated_toml(&without_nulls(document), sources)?,
+        Some("toml") => ConfigFormat::Toml.serialize(&without_nulls(document))?,
+        _ => table(document, annotate.then_some(sources)),
+    })
+}
+
+/// Finds the layer every value of `document` comes from: the last layer that sets it, or the
+/// defaults when no layer does.
+///
+/// # Arguments
+/// * `document` - The configuration to annotate.
+/// * `layers` - The layers merged into `document`, in merge order.
+///
+/// # Returns
+/// `Result<IndexMap<String, ConfigSource>>` - The source of every value, keyed by dotted path.
+fn value_sources(
+    document: &Value,
+    layers: &[ConfigLayer],
+) -> Result<IndexMap<String, ConfigSource>> {
+    // The paths set by each layer.
+    let layer_paths = layers
+        .iter()
+        .map(|layer| {
+            let value = serde_json::to_value(&layer.config)?;
+            let paths: HashSet<String> = leaf_values(&value).into_keys().collect();
+            Ok((&layer.source, paths))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(leaf_values(document)
+        .into_keys()
+        .map(|path| {
+            let source = layer_paths
+                .iter()
+                .rev()
+                .find(|(_, paths)| paths.contains(&path))
+                .map_or(ConfigSource::Default, |(source, _)| (*source).clone());
+            (path, source)
+        })
+        .collect())
+}
+
+/// Flattens a configuration into its leaf values keyed by dotted path.
+/// Arrays and empty objects count as single values.
+fn leaf_values(document: &Value) -> IndexMap<String, &Value> {
+    fn visit<'a>(value: &'a Value, path: String, leaves: &mut IndexMap<String, &'a Value>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    visit(value, path, leaves);
+                }
+            }
+            _ => {
+                leaves.insert(path, value);
+            }
+        }
+    }
+
+    let mut leaves = IndexMap::new();
+    visit(document, String::new(), &mut leaves);
+    leaves
+}
+
+/// Removes the unset values of a configuration, e.g. the neutral hue, which TOML cannot
+/// represent.
+fn without_nulls(document: &Value) -> Value {
+    match document {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), without_nulls(value)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Serializes a configuration as TOML with the source of each value as a trailing comment.
+fn annotated_toml(document: &Value, sources: &IndexMap<String, ConfigSource>) -> Result<String> {
+    let mut toml: DocumentMut = ConfigFormat::Toml.serialize(document)?.parse()?;
+    for (path, source) in sources {
+        let value = path
+            .split('.')
+            .try_fold(toml.as_item_mut(), |item, key| item.get_mut(key))
+            .and_then(|item| item.as_value_mut());
+        if let Some(value) = value {
+            value.decor_mut().set_suffix(format!(" # {}", source));
+        }
+    }
+    Ok(toml.to_string())
+}
+
+/// Formats a configuration as a table of dotted paths and values, with an optional column
+/// for the source of each value.
+fn table(document: &Value, sources: Option<&IndexMap<String, ConfigSource>>) -> String {
+    let rows: Vec<[String; 3]> = leaf_values(document)
+        .into_iter()
+        .map(|(path, value)| {
+            let source = sources
+                .and_then(|sources| sources.get(&path))
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            [path, value.to_string(), source]
+        })
+        .collect();
+
+    let width = |column: usize, header: &str| {
+        rows.iter()
+            .map(|row| row[column].chars().count())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let (path_width, value_width) = (width(0, "KEY"), width(1, "VALUE"));
+
+    let header = match sources {
+        Some(_) => format!("{:path_width$}  {:value_width$}  SOURCE", "KEY", "VALUE"),
+        None => format!("{:path_width$}  VALUE", "KEY"),
+    };
+    let mut output = format!("{}\n", header.trim_end().bold());
+    for [path, value, source] in rows {
+        let line = match sources {
+            Some(_) => format!(
+                "{:path_width$}  {:value_width$}  {}",
+                path,
+                value,
+                source.dimmed()
+            ),
+            None => format!("{:path_width$}  {}", path, value),
+        };
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{
+            extends::load_config_chain,
+            overrides::{cli_layers, variable_layers},
+            types::Config,
+            validate::display_path,
+        },
+        helpers::temp::TempDir,
+        ui::default::UI_CONFIGURATION,
+    };
+    use std::path::PathBuf;
+
+    /// Loads a configuration file with an environment variable and a `--set` override, and
+    /// returns the resolved configuration with its layers.
+    async fn resolved(directory: &TempDir) -> (PathBuf, Value, Vec<ConfigLayer>) {
+        let path = directory.write(
+            "seyuna.json",
+            r#"{ "ui": { "name": "Shop", "mode": "dark", "output_dir": "dist/css" } }"#,
+        );
+        let mut layers = load_config_chain(&path).await.unwrap();
+        layers.extend(
+            variable_layers([("SEYUNA_UI__MODE".to_string(), "light".to_string())].into_iter())
+                .unwrap(),
+        );
+        layers.extend(cli_layers(&["ui.output_dir=public/css".to_string()]).unwrap());
+
+        let default_configuration = Config {
+            license: None,
+            ui: Some(UI_CONFIGURATION.clone()),
+        };
+        let config = layers.iter().fold(default_configuration, |config, layer| {
+            config.merge(layer.config.clone())
+        });
+        (path, serde_json::to_value(&config).unwrap(), layers)
+    }
+
+    /// Removes the ANSI escape sequences of colored output.
+    fn plain(text: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = text.chars();
+        while let Some(char) = chars.next() {
+            if char == '\x1b' {
+                chars.by_ref().find(|char| *char == 'm');
+            } else {
+                plain.push(char);
+            }
+        }
+        plain
+    }
+
+    #[tokio::test]
+    async fn values_are_attributed_to_the_layer_that_sets_them() {
+        let directory = TempDir::new();
+        let (path, document, layers) = resolved(&directory).await;
+        let sources = value_sources(&document, &layers).unwrap();
+
+        assert_eq!(sources["ui.name"], ConfigSource::File(path));
+        assert_eq!(
+            sources["ui.mode"],
+            ConfigSource::Env("SEYUNA_UI__MODE".to_string())
+        );
+        assert_eq!(
+            sources["ui.output_dir"],
+            ConfigSource::Cli("ui.output_dir".to_string())
+        );
+        assert_eq!(sources["ui.build.minify"], ConfigSource::Default);
+        assert_eq!(sources["ui.theme.dark.lightness"], ConfigSource::Default);
+        // Every leaf has a source
+        assert_eq!(sources.len(), leaf_values(&document).len());
+    }
+
+    #[test]
+    fn arrays_and_empty_objects_are_leaves() {
+        let document = json!({ "a": { "b": [1, 2], "c": {}, "d": { "e": null } } });
+        let leaves: Vec<String> = leaf_values(&document).into_keys().collect();
+        assert_eq!(leaves, ["a.b", "a.c", "a.d.e"]);
+    }
+
+    #[tokio::test]
+    async fn json_output_lists_the_sources() {
+        let directory = TempDir::new();
+        let (path, document, layers) = resolved(&directory).await;
+        let sources = value_sources(&document, &layers).unwrap();
+
+        let output = format_output(&document, &sources, Some("json"), false).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&output).unwrap(), document);
+
+        let output = format_output(&document, &sources, Some("json"), true).unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["config"], document);
+        assert_eq!(output["sources"]["ui.name"], json!(display_path(&path)));
+        assert_eq!(output["sources"]["ui.mode"], json!("SEYUNA_UI__MODE"));
+        assert_eq!(
+            output["sources"]["ui.output_dir"],
+            json!("--set ui.output_dir")
+        );
+        assert_eq!(output["sources"]["ui.build.minify"], json!("default"));
+    }
+
+    #[tokio::test]
+    async fn toml_output_comments_the_sources() {
+        let directory = TempDir::new();
+        let (path, document, layers) = resolved(&directory).await;
+        let sources = value_sources(&document, &layers).unwrap();
+
+        // TOML has no null, so the unset neutral hue is left out
+        assert!(document["ui"]["theme"]["neutral"]["hue"].is_null());
+        let output = format_output(&document, &sources, Some("toml"), false).unwrap();
+        assert!(!output.contains('#'));
+        assert_eq!(
+            serde_json::to_value(toml::from_str::<toml::Value>(&output).unwrap()).unwrap(),
+            without_nulls(&document)
+        );
+
+        let output = format_output(&document, &sources, Some("toml"), true).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let name = format!(r#"name = "Shop" # {}"#, display_path(&path));
+        assert!(lines.contains(&name.as_str()), "{}", output);
+        assert!(lines.contains(&r#"mode = "light" # SEYUNA_UI__MODE"#));
+        assert!(lines.contains(&r#"output_dir = "public/css" # --set ui.output_dir"#));
+        assert!(lines.contains(&"minify = true # default"));
+    }
+
+    #[tokio::test]
+    async fn table_output_aligns_the_columns() {
+        let directory = TempDir::new();
+        let (_, document, layers) = resolved(&directory).await;
+        let sources = value_sources(&document, &layers).unwrap();
+
+        let output = plain(&format_output(&document, &sources, None, true).unwrap());
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), sources.len() + 1);
+        let value_column = lines[0].find("VALUE").unwrap();
+        let source_column = lines[0].find("SOURCE").unwrap();
+        let row = lines
+            .iter()
+            .find(|line| line.starts_with("ui.mode "))
+            .unwrap();
+        assert_eq!(
+            &row[value_column..],
+            format!(
+                "{:width$}SEYUNA_UI__MODE",
+                "\"light\"",
+                width = source_column - value_column
+            )
+        );
+
+        let output = plain(&format_output(&document, &sources, None, false).unwrap());
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(!lines[0].contains("SOURCE"));
+        assert!(
+            lines.contains(&format!("{:width$}\"Shop\"", "ui.name", width = value_column).as_str())
+        );
+    }
+}
@@ -1,24 +1,201 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use indexmap::IndexMap;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::ui;
+use crate::{
+    config::{
+        extends::Extends,
+        profile::selected_profile,
+        validate::{Diagnostic, display_path},
+        workspace::Workspace,
+    },
+    ui::{self, default::UI_CONFIGURATION},
+};
 
+/// The effective Seyuna configuration, with every UI value populated.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ui: Option<ui::types::UI>,
 }
 
+/// A Seyuna configuration as written by the user, e.g. in `seyuna.json`.
+/// Every field is optional and is layered over the configuration it is merged into.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+#[schemars(title = "Seyuna configuration")]
+pub struct PartialConfig {
+    /// The JSON schema of the configuration, for editor completion and validation.
+    #[serde(rename = "$schema", skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// The version of Seyuna the configuration is written for, upgraded by `config migrate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(example = &"1.0.0")]
+    pub version: Option<String>,
+    /// The versions of the Seyuna CLI allowed to load the configuration, as a semver
+    /// requirement, e.g. `^1.0.0`. A prerelease satisfies the `^` and `~` requirements of its
+    /// release, so `1.0.0-canary.2` satisfies `^1.0.0`, but not `>=1.0.0` or `=1.0.0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(example = &"^1.0.0")]
+    pub cli: Option<String>,
+    /// Configurations to inherit from: relative paths or package names from `node_modules`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<Extends>,
+    /// The Seyuna license key, e.g. from `SEYUNA_LICENSE` on build servers.
+    /// Takes precedence over the license activated with `seyuna license activate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// The Seyuna UI configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ui: Option<ui::types::PartialUI>,
+    /// Named overrides merged over the configuration when selected with `--profile` or
+    /// `SEYUNA_PROFILE`, e.g. `development` and `production`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<IndexMap<String, PartialConfig>>,
+    /// Member projects compiled together with this configuration as their root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<Workspace>,
+}
+
 impl Config {
-    pub fn merge(self, other: Self) -> Self {
+    pub fn merge(self, other: PartialConfig) -> Self {
         Self {
             license: other.license.or(self.license),
             ui: match (self.ui, other.ui) {
                 (Some(base), Some(override_)) => Some(base.merge(override_)),
-                (None, Some(override_)) => Some(override_),
+                (None, Some(override_)) => Some(UI_CONFIGURATION.clone().merge(override_)),
                 (Some(base), None) => Some(base),
                 (None, None) => None,
             },
         }
     }
 }
+
+/// Where a configuration value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The built-in default configuration.
+    Default,
+    /// A configuration file: the user's, or one it extends.
+    File(PathBuf),
+    /// A profile declared in a configuration file.
+    Profile { name: String, file: PathBuf },
+    /// An environment variable, e.g. `SEYUNA_UI__OUTPUT_DIR`.
+    Env(String),
+    /// A `--set` flag, holding the dotted path it sets.
+    Cli(String),
+}
+
+impl ConfigSource {
+    /// Returns the path of the file, if the source is a file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::File(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Default => write!(formatter, "default"),
+            Self::File(path) => write!(formatter, "{}", display_path(path)),
+            Self::Profile { name, file } => {
+                write!(formatter, "{} (profile {})", display_path(file), name)
+            }
+            Self::Env(name) => write!(formatter, "{}", name),
+            Self::Cli(path) => write!(formatter, "--set {}", path),
+        }
+    }
+}
+
+/// A partial configuration merged over the defaults, e.g. a single file of an `extends` chain.
+#[derive(Debug)]
+pub struct ConfigLayer {
+    pub source: ConfigSource,
+    pub config: PartialConfig,
+    /// Warnings found while validating the file.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Where to load the configuration from and what to override in it.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// An explicit path to the configuration file, e.g. from `--config`.
+    pub config_path: Option<PathBuf>,
+    /// The profile to merge over the configuration, from `--profile` or `SEYUNA_PROFILE`.
+    pub profile: Option<String>,
+    /// `path=value` assignments from `--set`, in command-line order.
+    pub overrides: Vec<String>,
+    /// Whether the configuration is a workspace member, which may leave out the profile
+    /// selected for the whole workspace.
+    pub workspace_member: bool,
+}
+
+impl LoadOptions {
+    /// Reads the global `--config`, `--profile` and `--set` options.
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        Self {
+            config_path: matches.get_one::<PathBuf>("config").cloned(),
+            profile: selected_profile(matches.get_one::<String>("profile")),
+            overrides: matches
+                .get_many::<String>("set")
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default(),
+            workspace_member: false,
+        }
+    }
+}
+
+/// A merged Seyuna configuration together with the layers it was merged from.
+#[derive(Debug)]
+pub struct LoadedConfig {
+    pub config: Config,
+    /// The user's configuration file.
+    pub path: PathBuf,
+    /// Every layer in merge order: the files of the `extends` chain ending with `path`,
+    /// then the selected profile, the environment variable and the `--set` overrides.
+    pub layers: Vec<ConfigLayer>,
+    /// The options the configuration was loaded with, to reload it the same way.
+    pub options: LoadOptions,
+}
+
+impl LoadedConfig {
+    /// Returns the warnings of every layer.
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.layers.iter().flat_map(|layer| &layer.diagnostics)
+    }
+
+    /// Returns the directory containing the configuration file.
+    /// Relative paths in the configuration are resolved against it.
+    pub fn root_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+
+    /// Returns the UI output directory resolved relative to the configuration file.
+    pub fn output_dir(&self) -> Result<PathBuf> {
+        let output_dir = self
+            .config
+            .ui
+            .as_ref()
+            .and_then(|ui| ui.output_dir.as_deref())
+            .context("Output directory missing in Seyuna config")?;
+
+        // Check if output_dir is empty
+        if output_dir.trim().is_empty() {
+            return Err(anyhow::anyhow!("Output directory cannot be empty"));
+        }
+
+        Ok(self.root_dir().join(output_dir))
+    }
+}
@@ -0,0 +1,1294 @@
+//! Validation of Seyuna configuration files.
+//!
+//! Produces [`Diagnostic`]s pointing at the exact file, line and column of a problem, for
+//! syntax errors, unknown keys, type mismatches and out-of-range values alike.
+
+use std::{
+    env, fmt,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use owo_colors::OwoColorize;
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+use serde::Serialize;
+use serde_json::{Number, Value, json};
+
+use crate::{
+    config::{
+        format::ConfigFormat,
+        locate::{Positions, line_column, value_positions},
+        migrate::{CONFIG_VERSION, config_version, needs_migration},
+        types::{ConfigSource, LoadOptions, LoadedConfig, PartialConfig},
+    },
+    helpers::{
+        cli::{CHECKMARK, error_text, primary_text, secondary_text},
+        fs::load_seyuna_user_config,
+    },
+    ui::{
+        types::{PartialColor, PartialNeutral, PartialPalette, PartialScale, ThemeColor, Tone},
+        validate::check_theme,
+    },
+};
+
+/// Chroma beyond which a color cannot be shown on any display.
+/// This is the reference range of `oklch()` in CSS Color 4, where `100%` equals `0.4`.
+pub const MAX_DISPLAYABLE_CHROMA: f64 = 0.4;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The configuration cannot be used.
+    Error,
+    /// The configuration can be used, but probably does not do what was intended.
+    Warning,
+}
+
+/// A problem found in a configuration file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    /// The environment variable or command-line flag of a value that is not read from a file.
+    pub source: Option<String>,
+    /// The dotted path of the offending value, e.g. `ui.theme.colors.alpha`.
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    #[serde(skip)]
+    source_line: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic that is not tied to a file.
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            file: None,
+            source: None,
+            path: None,
+            line: None,
+            column: None,
+            source_line: None,
+        }
+    }
+
+    /// Ties the diagnostic to a file.
+    pub fn in_file(mut self, file: &Path) -> Self {
+        self.file = Some(file.to_path_buf());
+        self
+    }
+
+    /// Ties the diagnostic to where a value comes from: a file, a variable or a flag.
+    pub fn in_source(self, source: &ConfigSource) -> Self {
+        match source {
+            ConfigSource::File(file) => self.in_file(file),
+            _ => Self {
+                source: Some(source.to_string()),
+                ..self
+            },
+        }
+    }
+
+    /// Ties the diagnostic to the value at a dotted path of a layer, reading the file the
+    /// layer comes from to locate it.
+    pub fn in_layer(self, source: &ConfigSource, path: &str) -> Self {
+        let (file, path) = match source {
+            ConfigSource::File(file) => (file, path.to_string()),
+            ConfigSource::Profile { name, file } => (file, format!("profiles.{}.{}", name, path)),
+            _ => return self.in_source(source).at_path("", &Positions::new(), path),
+        };
+        let content = fs::read_to_string(file).unwrap_or_default();
+        let positions = ConfigFormat::from_path(file)
+            .map(|format| value_positions(format, &content))
+            .unwrap_or_default();
+        self.in_file(file).at_path(&content, &positions, &path)
+    }
+
+    /// Ties the diagnostic to a 1-based line and column of `content`.
+    pub fn at(mut self, content: &str, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self.source_line = content
+            .lines()
+            .nth(line.saturating_sub(1))
+            .map(String::from);
+        self
+    }
+
+    /// Ties the diagnostic to the value at a dotted path, located with `positions`.
+    pub fn at_path(mut self, content: &str, positions: &Positions, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        match positions.get(path) {
+            Some(&offset) => {
+                let (line, column) = line_column(content, offset);
+                self.at(content, line, column)
+            }
+            None => self,
+        }
+    }
+
+    /// Renders the diagnostic with a code frame for the terminal.
+    ///
+    /// # Returns
+    /// `String` - The rendered diagnostic, e.g.
+    ///
+    /// ```text
+    /// error: hue must be between 0 and 360, found 400
+    ///   --> seyuna.json:5:16
+    ///    |
+    ///  5 |     "alpha": 400,
+    ///    |              ^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let label = match self.severity {
+            Severity::Error => secondary_text("error").bold().to_string(),
+            Severity::Warning => "warning".yellow().bold().to_string(),
+        };
+        let mut result = format!("{}: {}", label, self.message.bold());
+
+        let location = match (&self.file, &self.source, self.line, self.column) {
+            (Some(file), _, Some(line), Some(column)) => {
+                format!("{}:{}:{}", display_path(file), line, column)
+            }
+            (Some(file), _, _, _) => display_path(file),
+            (None, Some(source), _, _) => source.clone(),
+            (None, None, _, _) => return result,
+        };
+
+        let (Some(line), Some(column), Some(source_line)) =
+            (self.line, self.column, &self.source_line)
+        else {
+            result += &format!("\n  {} {}", "-->".blue(), location);
+            if let Some(path) = &self.path {
+                result += &format!("\n  {} at {}", "=".blue(), path);
+            }
+            return result;
+        };
+
+        let gutter = " ".repeat(line.to_string().len());
+        let marker = "^".repeat(token_width(source_line, column));
+        result += &format!("\n{} {} {}", gutter, "-->".blue(), location);
+        result += &format!("\n{} {}", gutter, "|".blue());
+        result += &format!("\n{} {} {}", line.blue(), "|".blue(), source_line);
+        result += &format!(
+            "\n{} {} {}{}",
+            gutter,
+            "|".blue(),
+            " ".repeat(column.saturating_sub(1)),
+            match self.severity {
+                Severity::Error => secondary_text(&marker),
+                Severity::Warning => marker.yellow().to_string(),
+            }
+        );
+        result
+    }
+}
+
+/// The error returned when a configuration file contains errors.
+/// Holds every diagnostic of the file, including warnings.
+#[derive(Debug)]
+pub struct ValidationError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "The configuration contains {}:",
+            plural(count(&self.diagnostics, Severity::Error), "error")
+        )?;
+        for diagnostic in &self.diagnostics {
+            write!(formatter, "\n\n{}", diagnostic.render())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Returns whether any of the diagnostics is an error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+/// Counts the diagnostics of the given severity.
+fn count(diagnostics: &[Diagnostic], severity: Severity) -> usize {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == severity)
+        .count()
+}
+
+/// Formats a count with the singular or plural form of `noun`, e.g. `1 error` or `2 errors`.
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Parses and validates the content of a single configuration file.
+///
+/// # Arguments
+/// * `path` - The path to the configuration file, used to detect its format.
+/// * `content` - The raw file content.
+///
+/// # Returns
+/// `(Option<PartialConfig>, Vec<Diagnostic>)` - The configuration if it could be parsed, and
+/// every problem found in it.
+pub fn validate_source(path: &Path, content: &str) -> (Option<PartialConfig>, Vec<Diagnostic>) {
+    let format = match ConfigFormat::from_path(path) {
+        Ok(format) => format,
+        Err(e) => {
+            let diagnostic = Diagnostic::new(Severity::Error, format!("{:#}", e)).in_file(path);
+            return (None, vec![diagnostic]);
+        }
+    };
+
+    // Syntax errors, unknown keys and type mismatches.
+    let config = match format.deserialize::<PartialConfig>(content) {
+        Ok(config) => config,
+        Err(e) => {
+            let mut diagnostic = Diagnostic::new(Severity::Error, e.message).in_file(path);
+            if let (Some(line), Some(column)) = (e.line, e.column) {
+                diagnostic = diagnostic.at(content, line, column);
+            }
+            let mut diagnostics = vec![diagnostic];
+
+            // Keys unknown to this CLI are often from the newer version the file requires.
+            if let Ok(document) = format.deserialize::<Value>(content)
+                && let Some(message) = document
+                    .get("cli")
+                    .and_then(Value::as_str)
+                    .and_then(unsatisfied_cli_requirement)
+            {
+                let positions = value_positions(format, content);
+                diagnostics.insert(
+                    0,
+                    Diagnostic::new(Severity::Error, message)
+                        .in_file(path)
+                        .at_path(content, &positions, "cli"),
+                );
+            }
+            return (None, diagnostics);
+        }
+    };
+
+    // Values outside of their valid range.
+    let mut validator = Validator {
+        source: ConfigSource::File(path.to_path_buf()),
+        content,
+        positions: value_positions(format, content),
+        diagnostics: Vec::new(),
+    };
+    validator.check(&config);
+
+    (Some(config), validator.diagnostics)
+}
+
+/// Validates the values of a configuration that is not read from a file, e.g. an override
+/// from an environment variable.
+///
+/// # Arguments
+/// * `source` - Where the configuration comes from.
+/// * `config` - The configuration to validate.
+///
+/// # Returns
+/// `Vec<Diagnostic>` - Every problem found in the configuration.
+pub fn validate_config(source: ConfigSource, config: &PartialConfig) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        source,
+        content: "",
+        positions: Positions::new(),
+        diagnostics: Vec::new(),
+    };
+    validator.check(config);
+    validator.diagnostics
+}
+
+/// Checks that the UI output directory can be created and written to, by creating and removing
+/// an empty file in it, or in its nearest existing parent. Only `compile` and `config validate`
+/// run the check, so loading the configuration never writes anything.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+///
+/// # Returns
+/// `Option<Diagnostic>` - An error if the output directory is not writable.
+pub fn check_output_dir(loaded: &LoadedConfig) -> Option<Diagnostic> {
+    // Point at the last file of the chain that sets the output directory.
+    let layer = loaded.layers.iter().rev().find(|layer| {
+        layer
+            .config
+            .ui
+            .as_ref()
+            .is_some_and(|ui| ui.output_dir.is_some())
+    });
+    let located = |diagnostic: Diagnostic| match layer {
+        Some(layer) => diagnostic.in_layer(&layer.source, "ui.output_dir"),
+        None => diagnostic.in_file(&loaded.path),
+    };
+
+    let output_dir = match loaded.output_dir() {
+        Ok(output_dir) => output_dir,
+        Err(e) => return Some(located(Diagnostic::new(Severity::Error, e.to_string()))),
+    };
+
+    // The directory is created on compile, so check the nearest directory that already exists.
+    let existing = output_dir
+        .ancestors()
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| ancestor.exists())?;
+
+    let message = if !existing.is_dir() {
+        format!(
+            "output_dir {:?} cannot be created because {:?} is not a directory",
+            output_dir, existing
+        )
+    } else {
+        let probe = existing.join(format!(".seyuna-write-check-{}", std::process::id()));
+        match File::create_new(&probe) {
+            Ok(_) => {
+                let _ = fs::remove_file(&probe);
+                return None;
+            }
+            Err(e) => format!("output_dir {:?} is not writable: {}", output_dir, e),
+        }
+    };
+
+    Some(located(Diagnostic::new(Severity::Error, message)))
+}
+
+/// Checks that the running CLI satisfies the `cli` requirement of every configuration file.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+///
+/// # Returns
+/// `Vec<Diagnostic>` - An error for every requirement this CLI does not satisfy.
+fn check_cli_version(loaded: &LoadedConfig) -> Vec<Diagnostic> {
+    loaded
+        .layers
+        .iter()
+        .filter_map(|layer| {
+            let message = unsatisfied_cli_requirement(layer.config.cli.as_deref()?)?;
+            Some(Diagnostic::new(Severity::Error, message).in_layer(&layer.source, "cli"))
+        })
+        .collect()
+}
+
+/// Checks a loaded configuration as a whole, across the files it is merged from: the `cli`
+/// requirement of every file, then the theme.
+/// Warnings are added to the layers they are about.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+///
+/// # Returns
+/// `Vec<Diagnostic>` - The errors that make the configuration unusable.
+pub fn check_loaded(loaded: &mut LoadedConfig) -> Vec<Diagnostic> {
+    // Refuse configurations written for another version of the CLI.
+    let diagnostics = check_cli_version(loaded);
+    if !diagnostics.is_empty() {
+        return diagnostics;
+    }
+    check_theme(loaded)
+}
+
+/// Returns an error message if this CLI does not satisfy a `cli` requirement.
+/// Invalid requirements are reported by the validator instead.
+fn unsatisfied_cli_requirement(requirement: &str) -> Option<String> {
+    unsatisfied_requirement(requirement, CONFIG_VERSION)
+}
+
+/// Returns an error message if a CLI version does not satisfy a `cli` requirement.
+///
+/// A prerelease also satisfies the `^` and `~` requirements naming its release, e.g.
+/// `1.0.0-canary.2` satisfies `^1.0.0`, which plain semver rejects. Other requirements, such
+/// as `>=1.0.0` or `=1.0.0`, follow plain semver.
+///
+/// # Arguments
+/// * `requirement` - The `cli` requirement.
+/// * `version` - The version of the CLI.
+///
+/// # Returns
+/// `Option<String>` - The error message, if the version does not satisfy the requirement.
+fn unsatisfied_requirement(requirement: &str, version: &str) -> Option<String> {
+    let requirement = VersionReq::parse(requirement).ok()?;
+    let current = Version::parse(version).ok()?;
+    let release = Version {
+        pre: Prerelease::EMPTY,
+        ..current.clone()
+    };
+    let names_release = |comparator: &Comparator| {
+        matches!(comparator.op, Op::Caret | Op::Tilde)
+            && (
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0),
+            ) == (release.major, release.minor, release.patch)
+    };
+    let prerelease_of_release = !current.pre.is_empty()
+        && requirement.matches(&release)
+        && requirement.comparators.iter().all(names_release);
+    (!requirement.matches(&current) && !prerelease_of_release).then(|| {
+        format!(
+            "the configuration requires Seyuna CLI {}, but {} is installed; install a \
+            matching version, e.g. `npm install -g @seyuna/cli@\"{}\"`",
+            requirement, current, requirement
+        )
+    })
+}
+
+/// Checks the values of a parsed configuration.
+struct Validator<'a> {
+    source: ConfigSource,
+    /// The raw file content, or nothing for configurations not read from a file.
+    content: &'a str,
+    positions: Positions,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, severity: Severity, path: &str, message: String) {
+        let diagnostic = Diagnostic::new(severity, message)
+            .in_source(&self.source)
+            .at_path(self.content, &self.positions, path);
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn check(&mut self, config: &PartialConfig) {
+        self.check_version(config);
+        if let Some(cli) = &config.cli
+            && VersionReq::parse(cli).is_err()
+        {
+            self.report(
+                Severity::Error,
+                "cli",
+                format!(
+                    "cli {:?} is not a valid version requirement, e.g. \"^1.0.0\"",
+                    cli
+                ),
+            );
+        }
+        self.check_config("", config);
+
+        for (name, profile) in config.profiles.iter().flatten() {
+            let prefix = format!("profiles.{}.", name);
+            for (key, set) in [
+                ("version", profile.version.is_some()),
+                ("cli", profile.cli.is_some()),
+                ("extends", profile.extends.is_some()),
+                ("profiles", profile.profiles.is_some()),
+                ("workspace", profile.workspace.is_some()),
+            ] {
+                if set {
+                    self.report(
+                        Severity::Error,
+                        &format!("{}{}", prefix, key),
+                        format!("{} cannot be used inside a profile", key),
+                    );
+                }
+            }
+            self.check_config(&prefix, profile);
+        }
+    }
+
+    /// Checks that the configuration is written for this version of Seyuna.
+    fn check_version(&mut self, config: &PartialConfig) {
+        let version = match config_version(config.version.as_deref(), config.schema.as_deref()) {
+            Ok(version) => version,
+            Err(e) => {
+                self.report(Severity::Error, "version", e.to_string());
+                return;
+            }
+        };
+        let path = if config.version.is_some() {
+            "version"
+        } else {
+            "$schema"
+        };
+
+        if let Some(version) = &version
+            && Version::parse(CONFIG_VERSION).is_ok_and(|current| *version > current)
+        {
+            self.report(
+                Severity::Warning,
+                path,
+                format!(
+                    "the configuration is written for Seyuna {}, which is newer than this CLI \
+                    ({}); some values may not be understood",
+                    version, CONFIG_VERSION
+                ),
+            );
+        } else if needs_migration(config, version.as_ref()) {
+            let written_for = version.map_or("an older version of Seyuna".to_string(), |version| {
+                format!("Seyuna {}", version)
+            });
+            self.report(
+                Severity::Warning,
+                path,
+                format!(
+                    "the configuration is written for {}; run `seyuna config migrate` to upgrade it",
+                    written_for
+                ),
+            );
+        }
+    }
+
+    /// Checks a configuration whose paths start with `prefix`, e.g. `profiles.production.`.
+    fn check_config(&mut self, prefix: &str, config: &PartialConfig) {
+        let Some(theme) = config.ui.as_ref().and_then(|ui| ui.theme.as_ref()) else {
+            return;
+        };
+
+        if let Some(colors) = &theme.colors {
+            for (name, color) in &colors.entries {
+                let path = format!("{}ui.theme.colors.{}", prefix, name);
+                if !is_css_identifier(name) {
+                    self.report(
+                        Severity::Error,
+                        &path,
+                        format!(
+                            "color name {:?} is not a valid CSS identifier; use letters, digits, \
+                            `-` and `_`, without a leading digit",
+                            name
+                        ),
+                    );
+                }
+                match color {
+                    Some(ThemeColor::Hue(hue)) => self.check_hue(&path, hue),
+                    Some(ThemeColor::Custom(color)) => {
+                        self.check_hue(&format!("{}.hue", path), &color.hue);
+                        for (mode, tone) in [("light", &color.light), ("dark", &color.dark)] {
+                            if let Some(tone) = tone {
+                                self.check_tone(&format!("{}.{}", path, mode), tone);
+                            }
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        for (mode, palette) in [("light", &theme.light), ("dark", &theme.dark)] {
+            if let Some(palette) = palette {
+                self.check_palette(&format!("{}ui.theme.{}", prefix, mode), palette);
+            }
+        }
+
+        if let Some(scale) = &theme.scale {
+            self.check_scale(&format!("{}ui.theme.scale", prefix), scale);
+        }
+        if let Some(neutral) = &theme.neutral {
+            self.check_neutral(&format!("{}ui.theme.neutral", prefix), neutral);
+        }
+        if let Some(semantic) = &theme.semantic {
+            for name in semantic.entries.keys() {
+                if !is_css_identifier(name) {
+                    self.report(
+                        Severity::Error,
+                        &format!("{}ui.theme.semantic.{}", prefix, name),
+                        format!(
+                            "semantic color name {:?} is not a valid CSS identifier; use letters, \
+                            digits, `-` and `_`, without a leading digit",
+                            name
+                        ),
+                    );
+                }
+            }
+        }
+        if let Some(on_colors) = &theme.on_colors {
+            let path = format!("{}ui.theme.on_colors", prefix);
+            if let Some(contrast) = &on_colors.contrast
+                && !(contrast.as_f64() == Some(0.0)
+                    || (1.0..=21.0).contains(&contrast.as_f64().unwrap_or_default()))
+            {
+                self.report(
+                    Severity::Error,
+                    &format!("{}.contrast", path),
+                    format!("contrast must be 0 or between 1 and 21, found {}", contrast),
+                );
+            }
+            if let Some(chroma) = &on_colors.chroma {
+                self.check_chroma(&format!("{}.chroma", path), chroma);
+            }
+        }
+    }
+
+    fn check_neutral(&mut self, path: &str, neutral: &PartialNeutral) {
+        if let Some(Some(hue)) = &neutral.hue {
+            self.check_hue(&format!("{}.hue", path), hue);
+        }
+        if let Some(chroma) = &neutral.chroma {
+            self.check_chroma(&format!("{}.chroma", path), chroma);
+        }
+        if let Some(step) = &neutral.surface_step
+            && !(0.0..=0.2).contains(&step.as_f64().unwrap_or_default())
+        {
+            self.report(
+                Severity::Error,
+                &format!("{}.surface_step", path),
+                format!("surface_step must be between 0 and 0.2, found {}", step),
+            );
+        }
+    }
+
+    fn check_scale(&mut self, path: &str, scale: &PartialScale) {
+        if let Some(steps) = scale.steps
+            && (steps == 1 || steps > 11)
+        {
+            self.report(
+                Severity::Error,
+                &format!("{}.steps", path),
+                format!("steps must be 0 or between 2 and 11, found {}", steps),
+            );
+        }
+        for (mode, curve) in [("light", &scale.light), ("dark", &scale.dark)] {
+            let Some(curve) = curve else {
+                continue;
+            };
+            let path = format!("{}.{}", path, mode);
+            for (name, lightness) in [("start", &curve.start), ("end", &curve.end)] {
+                if let Some(lightness) = lightness {
+                    self.check_lightness(&format!("{}.{}", path, name), lightness);
+                }
+            }
+            if let Some(curve) = &curve.curve
+                && curve.as_f64().unwrap_or_default() <= 0.0
+            {
+                self.report(
+                    Severity::Error,
+                    &format!("{}.curve", path),
+                    format!("curve must be above 0, found {}", curve),
+                );
+            }
+            if let Some(falloff) = &curve.chroma_falloff
+                && !(0.0..=1.0).contains(&falloff.as_f64().unwrap_or_default())
+            {
+                self.report(
+                    Severity::Error,
+                    &format!("{}.chroma_falloff", path),
+                    format!("chroma_falloff must be between 0 and 1, found {}", falloff),
+                );
+            }
+        }
+    }
+
+    fn check_palette(&mut self, path: &str, palette: &PartialPalette) {
+        if let Some(chroma) = &palette.chroma {
+            self.check_chroma(&format!("{}.chroma", path), chroma);
+        }
+        if let Some(lightness) = &palette.lightness {
+            self.check_lightness(&format!("{}.lightness", path), lightness);
+        }
+        for (name, color) in [("background", &palette.background), ("text", &palette.text)] {
+            if let Some(color) = color {
+                self.check_color(&format!("{}.{}", path, name), color);
+            }
+        }
+    }
+
+    fn check_tone(&mut self, path: &str, tone: &Tone) {
+        if let Some(chroma) = &tone.chroma {
+            self.check_chroma(&format!("{}.chroma", path), chroma);
+        }
+        if let Some(lightness) = &tone.lightness {
+            self.check_lightness(&format!("{}.lightness", path), lightness);
+        }
+    }
+
+    fn check_color(&mut self, path: &str, color: &PartialColor) {
+        if let Some(hue) = &color.hue {
+            self.check_hue(&format!("{}.hue", path), hue);
+        }
+        if let Some(chroma) = &color.chroma {
+            self.check_chroma(&format!("{}.chroma", path), chroma);
+        }
+        if let Some(lightness) = &color.lightness {
+            self.check_lightness(&format!("{}.lightness", path), lightness);
+        }
+    }
+
+    fn check_hue(&mut self, path: &str, hue: &Number) {
+        let value = hue.as_f64().unwrap_or_default();
+        if !(0.0..=360.0).contains(&value) {
+            self.report(
+                Severity::Error,
+                path,
+                format!("hue must be between 0 and 360, found {}", hue),
+            );
+        }
+    }
+
+    fn check_lightness(&mut self, path: &str, lightness: &Number) {
+        let value = lightness.as_f64().unwrap_or_default();
+        if !(0.0..=1.0).contains(&value) {
+            self.report(
+                Severity::Error,
+                path,
+                format!("lightness must be between 0 and 1, found {}", lightness),
+            );
+        }
+    }
+
+    fn check_chroma(&mut self, path: &str, chroma: &Number) {
+        let value = chroma.as_f64().unwrap_or_default();
+        if value < 0.0 {
+            self.report(
+                Severity::Error,
+                path,
+                format!("chroma cannot be negative, found {}", chroma),
+            );
+        } else if value > MAX_DISPLAYABLE_CHROMA {
+            self.report(
+                Severity::Warning,
+                path,
+                format!(
+                    "chroma {} is above {} and cannot be shown on any display; colors are shown \
+                    with less chroma",
+                    chroma, MAX_DISPLAYABLE_CHROMA
+                ),
+            );
+        }
+    }
+}
+
+/// Returns whether `name` is a valid CSS identifier.
+pub fn is_css_identifier(name: &str) -> bool {
+    let is_name_start = |c: char| c == '_' || c.is_ascii_alphabetic() || !c.is_ascii();
+    let is_name = |c: char| is_name_start(c) || c == '-' || c.is_ascii_digit();
+
+    let mut chars = name.strip_prefix('-').unwrap_or(name).chars();
+    match chars.next() {
+        Some(c) if c == '-' || is_name_start(c) => chars.all(is_name),
+        _ => false,
+    }
+}
+
+/// Returns the width of the token starting at the 1-based `column` of `line`.
+fn token_width(line: &str, column: usize) -> usize {
+    let rest: Vec<char> = line.chars().skip(column.saturating_sub(1)).collect();
+    let width = match rest.first() {
+        Some(&quote @ ('"' | '\'')) => rest[1..]
+            .iter()
+            .position(|&c| c == quote)
+            .map_or(rest.len(), |end| end + 2),
+        _ => rest
+            .iter()
+            .position(|c| matches!(c, ',' | '}' | ']' | '#') || c.is_whitespace())
+            .unwrap_or(rest.len()),
+    };
+    width.max(1)
+}
+
+/// Returns `path` relative to the current directory when it is inside it.
+pub fn display_path(path: &Path) -> String {
+    env::current_dir()
+        .ok()
+        .and_then(|current_dir| path.strip_prefix(current_dir).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Builds the report printed by `config validate --format json`.
+fn json_report(diagnostics: &[Diagnostic]) -> Value {
+    json!({
+        "valid": !has_errors(diagnostics),
+        "errors": count(diagnostics, Severity::Error),
+        "warnings": count(diagnostics, Severity::Warning),
+        "diagnostics": diagnostics,
+    })
+}
+
+/// Entrypoint for the `config validate` CLI command.
+/// Validates the configuration file and everything it extends.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+pub async fn validate(matches: &ArgMatches) {
+    let diagnostics = match load_seyuna_user_config(&LoadOptions::from_matches(matches)).await {
+        Ok(loaded) => loaded
+            .diagnostics()
+            .cloned()
+            .chain(check_output_dir(&loaded))
+            .collect(),
+        Err(e) => match e.downcast_ref::<ValidationError>() {
+            Some(error) => error.diagnostics.clone(),
+            None => vec![Diagnostic::new(Severity::Error, format!("{:#}", e))],
+        },
+    };
+    let valid = !has_errors(&diagnostics);
+
+    if matches.get_one::<String>("format").map(String::as_str) == Some("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json_report(&diagnostics)).unwrap()
+        );
+    } else {
+        for diagnostic in &diagnostics {
+            eprintln!("{}\n", diagnostic.render());
+        }
+        if valid {
+            println!(
+                "{} {}",
+                *CHECKMARK,
+                primary_text("The configuration is valid")
+            );
+        } else {
+            eprintln!(
+                "{}",
+                error_text(&format!(
+                    "Found {} and {}",
+                    plural(count(&diagnostics, Severity::Error), "error"),
+                    plural(count(&diagnostics, Severity::Warning), "warning")
+                ))
+            );
+        }
+    }
+
+    if !valid {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::temp::TempDir;
+
+    /// Loads a configuration file written to a temporary directory.
+    async fn load(directory: &TempDir, content: &str) -> Result<LoadedConfig, Vec<Diagnostic>> {
+        let options = LoadOptions {
+            config_path: Some(directory.write("seyuna.json", content)),
+            ..LoadOptions::default()
+        };
+        load_seyuna_user_config(&options)
+            .await
+            .map_err(|e| match e.downcast::<ValidationError>() {
+                Ok(error) => error.diagnostics,
+                Err(e) => panic!("{:#}", e),
+            })
+    }
+
+    #[tokio::test]
+    async fn output_dir_check_leaves_nothing_behind() {
+        let directory = TempDir::new();
+        let loaded = load(&directory, r#"{ "ui": { "output_dir": "dist/css" } }"#)
+            .await
+            .unwrap();
+
+        assert!(check_output_dir(&loaded).is_none());
+        let entries = std::fs::read_dir(directory.path()).unwrap().count();
+        assert_eq!(entries, 1, "only seyuna.json is left");
+    }
+
+    #[tokio::test]
+    async fn output_dir_under_a_file_is_an_error() {
+        let directory = TempDir::new();
+        directory.write("dist", "");
+        let loaded = load(&directory, r#"{ "ui": { "output_dir": "dist/css" } }"#)
+            .await
+            .unwrap();
+
+        let diagnostic = check_output_dir(&loaded).unwrap();
+        assert!(diagnostic.message.contains("is not a directory"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unwritable_output_dir_is_an_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = TempDir::new();
+        let loaded = load(&directory, r#"{ "ui": { "output_dir": "locked/css" } }"#)
+            .await
+            .unwrap();
+        let locked = directory.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Privileged users write through permissions, so only check what the probe can see
+        let writable = File::create(locked.join("probe")).is_ok();
+        let diagnostic = check_output_dir(&loaded);
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        if writable {
+            assert!(diagnostic.is_none());
+        } else {
+            let diagnostic = diagnostic.unwrap();
+            assert_eq!(diagnostic.severity, Severity::Error);
+            assert!(diagnostic.message.contains("is not writable"));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn output_dir_in_a_read_only_file_system_is_an_error() {
+        let directory = TempDir::new();
+        let loaded = load(&directory, r#"{ "ui": { "output_dir": "/proc/seyuna" } }"#)
+            .await
+            .unwrap();
+
+        let diagnostic = check_output_dir(&loaded).unwrap();
+        assert!(diagnostic.message.contains("is not writable"));
+    }
+
+    /// Validates a configuration file and returns the severity, path and message of every
+    /// diagnostic.
+    fn diagnostics_of(file: &str, content: &str) -> Vec<(Severity, String, String)> {
+        let (_, diagnostics) = validate_source(Path::new(file), content);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.severity,
+                    diagnostic.path.unwrap_or_default(),
+                    diagnostic.message,
+                )
+            })
+            .collect()
+    }
+
+    /// Removes the terminal colors of rendered text.
+    fn plain(text: &str) -> String {
+        let mut result = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\u{1b}' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                result.push(c);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn unknown_keys_are_errors() {
+        let (config, diagnostics) = validate_source(
+            Path::new("seyuna.json"),
+            "{\n  \"ui\": {\n    \"nmae\": \"Seyuna\"\n  }\n}\n",
+        );
+        assert!(config.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(
+            diagnostics[0].message.contains("unknown field `nmae`"),
+            "{}",
+            diagnostics[0].message
+        );
+        assert_eq!(diagnostics[0].line, Some(3));
+    }
+
+    #[test]
+    fn hues_must_be_between_0_and_360() {
+        let diagnostics = diagnostics_of(
+            "seyuna.json",
+            r#"{ "ui": { "theme": { "colors": { "a": 0, "b": 360, "c": 400, "d": { "hue": -1 } } } } }"#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                (
+                    Severity::Error,
+                    "ui.theme.colors.c".to_string(),
+                    "hue must be between 0 and 360, found 400".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "ui.theme.colors.d.hue".to_string(),
+                    "hue must be between 0 and 360, found -1".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lightness_must_be_between_0_and_1() {
+        let diagnostics = diagnostics_of(
+            "seyuna.json",
+            r#"{ "ui": { "theme": { "light": { "lightness": 0 }, "dark": { "lightness": 1.5 } } } }"#,
+        );
+        assert_eq!(
+            diagnostics,
+            [(
+                Severity::Error,
+                "ui.theme.dark.lightness".to_string(),
+                "lightness must be between 0 and 1, found 1.5".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn chroma_is_limited() {
+        let diagnostics = diagnostics_of(
+            "seyuna.json",
+            r#"{ "ui": { "theme": { "light": { "chroma": -0.1 }, "dark": { "chroma": 0.5 } } } }"#,
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0],
+            (
+                Severity::Error,
+                "ui.theme.light.chroma".to_string(),
+                "chroma cannot be negative, found -0.1".to_string()
+            )
+        );
+        assert_eq!(diagnostics[1].0, Severity::Warning);
+        assert_eq!(diagnostics[1].1, "ui.theme.dark.chroma");
+        assert!(diagnostics[1].2.contains("above 0.4"));
+
+        let diagnostics = diagnostics_of(
+            "seyuna.json",
+            r#"{ "ui": { "theme": { "light": { "chroma": 0.4 } } } }"#,
+        );
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn scales_have_no_steps_or_two_to_eleven() {
+        for steps in [0, 2, 11] {
+            let content = format!(
+                r#"{{ "ui": {{ "theme": {{ "scale": {{ "steps": {} }} }} }} }}"#,
+                steps
+            );
+            assert!(
+                diagnostics_of("seyuna.json", &content).is_empty(),
+                "{}",
+                steps
+            );
+        }
+        for steps in [1, 12] {
+            let content = format!(
+                r#"{{ "ui": {{ "theme": {{ "scale": {{ "steps": {} }} }} }} }}"#,
+                steps
+            );
+            assert_eq!(
+                diagnostics_of("seyuna.json", &content),
+                [(
+                    Severity::Error,
+                    "ui.theme.scale.steps".to_string(),
+                    format!("steps must be 0 or between 2 and 11, found {}", steps)
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn color_names_must_be_css_identifiers() {
+        for name in [
+            "brand",
+            "brand-blue",
+            "_private",
+            "-webkit",
+            "--custom",
+            "été",
+        ] {
+            assert!(is_css_identifier(name), "{}", name);
+        }
+        for name in ["", "1st", "-1st", "brand blue", "brand.blue", "-"] {
+            assert!(!is_css_identifier(name), "{}", name);
+        }
+
+        let diagnostics = diagnostics_of(
+            "seyuna.json",
+            r#"{ "ui": { "theme": { "colors": { "1st": 10 }, "semantic": { "my action": "1st" } } } }"#,
+        );
+        let paths: Vec<_> = diagnostics
+            .iter()
+            .map(|(_, path, _)| path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            ["ui.theme.colors.1st", "ui.theme.semantic.my action"]
+        );
+        assert!(diagnostics[0].2.contains("is not a valid CSS identifier"));
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_line_and_column_of_the_value() {
+        for (file, content, line, column) in [
+            (
+                "seyuna.json",
+                "{\n  \"ui\": {\n    \"theme\": {\n      \"colors\": { \"alpha\": 400 }\n    }\n  }\n}\n",
+                4,
+                28,
+            ),
+            (
+                "seyuna.jsonc",
+                "{\n  // The brand colors\n  \"ui\": { \"theme\": { \"colors\": {\n    \"alpha\": 400 // too far\n  } } }\n}\n",
+                4,
+                14,
+            ),
+            ("seyuna.toml", "[ui.theme.colors]\nalpha = 400\n", 2, 9),
+            (
+                "seyuna.yaml",
+                "ui:\n  theme:\n    colors:\n      # The first color\n      alpha: 400\n",
+                5,
+                14,
+            ),
+        ] {
+            let (_, diagnostics) = validate_source(Path::new(file), content);
+            assert_eq!(diagnostics.len(), 1, "{}", file);
+            let diagnostic = &diagnostics[0];
+            assert_eq!(
+                (diagnostic.line, diagnostic.column),
+                (Some(line), Some(column)),
+                "{}",
+                file
+            );
+            assert_eq!(diagnostic.file.as_deref(), Some(Path::new(file)));
+        }
+    }
+
+    #[test]
+    fn positions_cover_nested_values() {
+        let content =
+            "{\"ui\": {\"theme\": {\"colors\": {\"alpha\": 1}}}, \"extends\": [\"a\", \"b\"]}";
+        let positions = value_positions(ConfigFormat::Json, content);
+        assert_eq!(
+            positions["ui.theme.colors.alpha"],
+            content.find('1').unwrap()
+        );
+        assert_eq!(positions["extends.1"], content.find("\"b\"").unwrap());
+
+        let content = "extends:\n  - a\n  - b\nui:\n  name: Seyuna\n";
+        let positions = value_positions(ConfigFormat::Yaml, content);
+        assert_eq!(positions["extends.1"], content.find('b').unwrap());
+        assert_eq!(positions["ui.name"], content.find("Seyuna").unwrap());
+    }
+
+    #[test]
+    fn diagnostics_render_a_code_frame() {
+        let (_, diagnostics) = validate_source(
+            Path::new("seyuna.json"),
+            "{\n  \"ui\": {\n    \"theme\": {\n      \"colors\": { \"alpha\": 400 }\n    }\n  }\n}\n",
+        );
+        assert_eq!(
+            plain(&diagnostics[0].render()),
+            "error: hue must be between 0 and 360, found 400\n  \
+            --> seyuna.json:4:28\n  \
+            |\n\
+            4 |       \"colors\": { \"alpha\": 400 }\n  \
+            |                            ^^^"
+        );
+
+        // Values that are not read from a file point at their source instead.
+        let diagnostic = Diagnostic::new(Severity::Warning, "chroma is high")
+            .in_source(&ConfigSource::Env(
+                "SEYUNA_UI__THEME__LIGHT__CHROMA".to_string(),
+            ))
+            .at_path("", &Positions::new(), "ui.theme.light.chroma");
+        assert_eq!(
+            plain(&diagnostic.render()),
+            "warning: chroma is high\n  \
+            --> SEYUNA_UI__THEME__LIGHT__CHROMA\n  \
+            = at ui.theme.light.chroma"
+        );
+    }
+
+    #[test]
+    fn json_reports_count_errors_and_warnings() {
+        let (_, diagnostics) = validate_source(
+            Path::new("seyuna.json"),
+            r#"{ "ui": { "theme": { "colors": { "alpha": 400 }, "dark": { "chroma": 0.5 } } } }"#,
+        );
+        let report = json_report(&diagnostics);
+        assert_eq!(report["valid"], false);
+        assert_eq!(report["errors"], 1);
+        assert_eq!(report["warnings"], 1);
+        assert_eq!(
+            report["diagnostics"][0],
+            json!({
+                "severity": "error",
+                "message": "hue must be between 0 and 360, found 400",
+                "file": "seyuna.json",
+                "source": null,
+                "path": "ui.theme.colors.alpha",
+                "line": 1,
+                "column": 43,
+            })
+        );
+        assert_eq!(report["diagnostics"][1]["severity"], "warning");
+
+        assert_eq!(
+            json_report(&[]),
+            json!({ "valid": true, "errors": 0, "warnings": 0, "diagnostics": [] })
+        );
+    }
+
+    #[test]
+    fn prereleases_satisfy_the_caret_and_tilde_requirements_of_their_release() {
+        for (requirement, version) in [
+            ("^1.0.0", "1.0.0-canary.2"),
+            ("^1.0.0", "1.4.2"),
+            (">=1.0.0-canary.1", "1.0.0-canary.2"),
+            ("~1.2", "1.2.0-beta.1"),
+            ("~1.2.0", "1.2.0-beta.1"),
+        ] {
+            assert_eq!(
+                unsatisfied_requirement(requirement, version),
+                None,
+                "{} {}",
+                requirement,
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn unsatisfied_cli_requirements_are_errors() {
+        for (requirement, version) in [
+            ("^2.0.0", "1.0.0-canary.2"),
+            (">=1.0.1", "1.0.0-canary.2"),
+            ("^1.0.0", "0.9.0"),
+            // Prereleases satisfy only the caret and tilde requirements of their own release
+            (">=1.0.0", "1.0.0-canary.2"),
+            ("=1.0.0", "1.0.0-canary.2"),
+            ("^1.0.0", "1.1.0-beta.1"),
+            ("^1.0.0, <2.0.0", "1.0.0-canary.2"),
+        ] {
+            let message = unsatisfied_requirement(requirement, version).unwrap();
+            assert!(
+                message.contains(&format!(
+                    "requires Seyuna CLI {}, but {} is installed",
+                    VersionReq::parse(requirement).unwrap(),
+                    version
+                )),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn cli_requirement_is_checked_on_load() {
+        let directory = TempDir::new();
+        assert!(load(&directory, r#"{ "cli": "^1.0.0" }"#).await.is_ok());
+
+        let diagnostics = load(&directory, r#"{ "cli": ">=99.0.0" }"#)
+            .await
+            .unwrap_err();
+        assert!(
+            diagnostics[0]
+                .message
+                .contains("requires Seyuna CLI >=99.0.0")
+        );
+    }
+}
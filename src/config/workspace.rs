@@ -0,0 +1,82 @@
+//! Workspaces: one root configuration compiling the UI of many projects.
+//!
+//! The root configuration lists its member directories, as paths or glob patterns relative
+//! to the root, and every member directory has its own configuration file:
+//!
+//! ```json
+//! { "workspace": { "members": ["apps/*", "packages/design-system"] } }
+//! ```
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+use indexmap::IndexSet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::types::LoadedConfig, helpers::fs::find_config_in_dir};
+
+/// The `workspace` section of a root configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Workspace {
+    /// Member directories, as paths or glob patterns relative to the root configuration.
+    pub members: Vec<String>,
+}
+
+/// Returns the configuration files of the workspace members, if the user's configuration file
+/// declares a workspace. Only the user's own file counts: `workspace` in an extended
+/// configuration is ignored, so members can extend the root to share its theme.
+///
+/// Directories matched by a glob pattern without a configuration file are skipped, but every
+/// pattern must match at least one member. The root itself can be listed as `"."`; members are
+/// compiled as single projects, even when they declare a workspace themselves.
+///
+/// # Arguments
+/// * `loaded` - The root configuration.
+///
+/// # Returns
+/// `Result<Option<Vec<PathBuf>>>` - The members' configuration files, in declaration order.
+pub fn workspace_members(loaded: &LoadedConfig) -> Result<Option<Vec<PathBuf>>> {
+    // Extended files are merged first, so the user's file is the last file layer.
+    let Some(workspace) = loaded
+        .layers
+        .iter()
+        .rev()
+        .find(|layer| layer.source.path().is_some())
+        .and_then(|layer| layer.config.workspace.as_ref())
+    else {
+        return Ok(None);
+    };
+
+    let root = loaded.root_dir();
+    let mut members = IndexSet::new();
+    for pattern in &workspace.members {
+        let full_pattern = root.join(pattern);
+        let full_pattern = full_pattern
+            .to_str()
+            .with_context(|| format!("Workspace member {:?} is not valid UTF-8", pattern))?;
+
+        let matches = glob::glob(full_pattern)
+            .with_context(|| format!("Invalid workspace member pattern {:?}", pattern))?;
+        let mut found = false;
+        for directory in matches {
+            let directory = directory
+                .with_context(|| format!("Failed to read workspace member {:?}", pattern))?;
+            let directory = directory.canonicalize().unwrap_or(directory);
+            if let Some(config_file) = find_config_in_dir(&directory) {
+                members.insert(config_file);
+                found = true;
+            }
+        }
+
+        if !found {
+            return Err(anyhow!(
+                "Workspace member {:?} does not match any directory with a Seyuna configuration file",
+                pattern
+            ));
+        }
+    }
+
+    Ok(Some(members.into_iter().collect()))
+}
@@ -61,6 +61,9 @@ pub fn error_text(text: &str) -> String {
 /// A static checkmark symbol with primary gradient styling.
 pub static CHECKMARK: Lazy<String> = Lazy::new(|| primary_text("✔"));
 
+/// A static cross symbol with secondary gradient styling.
+pub static CROSSMARK: Lazy<String> = Lazy::new(|| secondary_text("✘"));
+
 /// Starts a spinner with the given text and a short delay for effect.
 ///
 /// # Arguments
@@ -69,8 +72,7 @@ pub static CHECKMARK: Lazy<String> = Lazy::new(|| primary_text("✔"));
 /// # Returns
 /// `RunningSpinner` - Instance.
 pub fn spinner_start(text: &str) -> RunningSpinner {
-    let s = Spinner::new(text).start();
-    s
+    Spinner::new(text).start()
 }
 
 /// Updates the spinner's message and waits briefly.
@@ -90,3 +92,12 @@ pub fn spinner_progress(s: &RunningSpinner, text: &str) {
 pub fn spinner_done(s: &RunningSpinner, text: &str) {
     s.text(&primary_text(text)).symbol(&CHECKMARK).stop();
 }
+
+/// Stops the spinner, sets a cross, and displays a final message.
+///
+/// # Arguments
+/// * `s` - The running spinner.
+/// * `text` - The failure message.
+pub fn spinner_failed(s: &RunningSpinner, text: &str) {
+    s.text(&secondary_text(text)).symbol(&CROSSMARK).stop();
+}
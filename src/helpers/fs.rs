@@ -3,16 +3,27 @@
 //! This module provides async functions for reading, writing, and loading configuration files,
 //! as well as path construction helpers.
 
-use crate::config::types::Config;
+use crate::config::extends::load_config_chain;
+use crate::config::format::{CONFIG_FILE_EXTENSIONS, CONFIG_FILE_STEM};
+use crate::config::overrides::{cli_layers, env_layers};
+use crate::config::profile::profile_layers;
+use crate::config::types::{Config, LoadOptions, LoadedConfig, PartialConfig};
+use crate::config::validate::{
+    Diagnostic, ValidationError, check_loaded, has_errors, validate_source,
+};
 use crate::ui::default::UI_CONFIGURATION;
 use anyhow::{Context, Result};
-use serde_json::Value;
+use std::env;
 use std::path::{Path, PathBuf};
 use tokio::{
     fs::{self, File},
     io::AsyncWriteExt,
 };
 
+/// Entries that mark the root of a version-controlled project.
+/// Configuration discovery never walks above a directory containing one of these.
+const VCS_ROOT_MARKERS: [&str; 3] = [".git", ".hg", ".svn"];
+
 /// Constructs a full path from a file name and output directory.
 ///
 /// # Arguments
@@ -21,22 +32,19 @@ use tokio::{
 ///
 /// # Returns
 /// `Result<PathBuf>` - The full path including the filename.
-pub fn create_path_from_file_name(file_name: &str, output_dir: &str) -> Result<PathBuf> {
-    // Path to the output directory.
-    let directory = Path::new(output_dir);
-
+pub fn create_path_from_file_name(file_name: &str, output_dir: &Path) -> Result<PathBuf> {
     // Check if file_name is empty
     if file_name.trim().is_empty() {
         return Err(anyhow::anyhow!("File name cannot be empty"));
     }
 
     // Check if output_dir is empty
-    if output_dir.trim().is_empty() {
+    if output_dir.as_os_str().is_empty() {
         return Err(anyhow::anyhow!("Output directory cannot be empty"));
     }
 
     // Path including the filename.
-    let path = directory.join(file_name);
+    let path = output_dir.join(file_name);
 
     // Return the full path.
     Ok(path)
@@ -80,42 +88,276 @@ pub async fn save_file<P: AsRef<Path>>(path: P, content: &[u8]) -> Result<()> {
         .with_context(|| format!("Failed to create file: {:?}", path_ref))?;
 
     // Write the given contents to the file.
-    file.write_all(&content)
+    file.write_all(content)
         .await
         .with_context(|| format!("Failed to write to file: {:?}", path_ref))?;
 
     Ok(())
 }
 
-/// Loads the user's Seyuna configuration from `seyuna.json` and merges it with the default configuration.
+/// Finds the nearest Seyuna configuration file by walking up from `start` through its parent
+/// directories. Within a directory, `seyuna.json` wins over `seyuna.jsonc`, `seyuna.toml`,
+/// `seyuna.yaml` and `seyuna.yml`, in that order.
+///
+/// The search stops at the first directory that is the root of a version-controlled
+/// project (contains `.git`, `.hg` or `.svn`), so configuration from unrelated
+/// projects higher up the tree is never picked up.
+///
+/// # Arguments
+/// * `start` - The directory to start searching from.
+///
+/// # Returns
+/// `Result<PathBuf>` - The path to the nearest configuration file.
+pub fn find_config_file(start: &Path) -> Result<PathBuf> {
+    for directory in start.ancestors() {
+        // Return the configuration file if this directory contains one.
+        if let Some(config_file) = find_config_in_dir(directory) {
+            return Ok(config_file);
+        }
+
+        // Do not search beyond the root of the project.
+        if VCS_ROOT_MARKERS
+            .iter()
+            .any(|marker| directory.join(marker).exists())
+        {
+            break;
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "Could not find {}.{{{}}} in {:?} or any of its parent directories. \
+        Run `seyuna config --init` to create one, or pass its location with --config.",
+        CONFIG_FILE_STEM,
+        CONFIG_FILE_EXTENSIONS.join(","),
+        start
+    ))
+}
+
+/// Finds the Seyuna configuration file directly inside `directory`, without searching its
+/// parents.
+///
+/// # Arguments
+/// * `directory` - The directory to look in.
+///
+/// # Returns
+/// `Option<PathBuf>` - The path to the configuration file, if the directory has one.
+pub fn find_config_in_dir(directory: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_EXTENSIONS
+        .iter()
+        .map(|extension| directory.join(format!("{}.{}", CONFIG_FILE_STEM, extension)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Returns the explicit configuration path if given, otherwise the nearest configuration file
+/// found by [`find_config_file`] from the current working directory.
 ///
-/// If the file is missing or invalid, prints an error and returns the error.
+/// # Arguments
+/// * `config_path` - An explicit path to the configuration file, e.g. from `--config`.
 ///
 /// # Returns
-/// `Result<Config>` - The Seyuna configuration object.
-pub async fn load_seyuna_user_config() -> Result<Config> {
-    // Find and read the 'seyuna.json' file in the current directory.
-    read_file("seyuna.json")
+/// `Result<PathBuf>` - The path to the configuration file.
+pub fn resolve_config_path(config_path: Option<&Path>) -> Result<PathBuf> {
+    match config_path {
+        Some(path) => Ok(path.to_path_buf()),
+        None => find_config_file(&env::current_dir()?),
+    }
+}
+
+/// Reads, parses and validates a single configuration file, without resolving `extends`.
+///
+/// Fails with a [`ValidationError`] if the file contains errors.
+///
+/// # Arguments
+/// * `path` - The path to the configuration file.
+///
+/// # Returns
+/// `Result<(PartialConfig, Vec<Diagnostic>)>` - The configuration exactly as written in the
+/// file, and the warnings found in it.
+pub async fn read_config_file(path: &Path) -> Result<(PartialConfig, Vec<Diagnostic>)> {
+    // Read the configuration file.
+    let content = read_file(path)
         .await
-        .context(
-            "Could not find seyuna.json file. Please make sure the file exists in the directory.",
-        )
-        .and_then(|content| {
-            // Parse the content as JSON.
-            let json: Value =
-                serde_json::from_str(&content).context("Failed to parse seyuna.json as JSON")?;
-
-            // Map the parsed JSON to the Seyuna configuration type.
-            let user_config: Config = serde_json::from_value(json)
-                .context("seyuna.json file does not match the Seyuna configuration type.")?;
-
-            // Load the default Seyuna configuration.
-            let default_configuration = Config {
-                license: None,
-                ui: Some(UI_CONFIGURATION.clone()),
-            };
-
-            // Return the merged Seyuna configuration.
-            Ok(default_configuration.merge(user_config))
-        })
+        .with_context(|| format!("Could not read the Seyuna configuration file {:?}.", path))?;
+
+    // Parse the content according to the file extension and validate it.
+    match validate_source(path, &content) {
+        (Some(config), diagnostics) if !has_errors(&diagnostics) => Ok((config, diagnostics)),
+        (_, diagnostics) => Err(ValidationError { diagnostics }.into()),
+    }
+}
+
+/// Loads the user's Seyuna configuration and merges it with the default configuration.
+///
+/// When no configuration path is given, the configuration file is discovered with
+/// [`find_config_file`] starting from the current working directory. Layers are merged in
+/// this order, each overriding the ones before it:
+///
+/// 1. The built-in defaults
+/// 2. Configurations listed in `extends`, before the file that extends them
+/// 3. The configuration file
+/// 4. The selected profile, from every file that declares it
+/// 5. `SEYUNA_UI__*` environment variables
+/// 6. `--set` flags
+///
+/// # Arguments
+/// * `options` - The configuration path and overrides.
+///
+/// # Returns
+/// `Result<LoadedConfig>` - The Seyuna configuration object and the layers it was merged from.
+pub async fn load_seyuna_user_config(options: &LoadOptions) -> Result<LoadedConfig> {
+    let path = resolve_config_path(options.config_path.as_deref())?;
+
+    // Load the configuration file along with everything it extends, then the overrides.
+    let mut layers = load_config_chain(&path).await?;
+    if let Some(profile) = &options.profile {
+        // A workspace root leaves the profile to its members
+        let root = layers
+            .last()
+            .is_some_and(|layer| layer.config.workspace.is_some());
+        let required = !options.workspace_member && !root;
+        layers.extend(profile_layers(&layers, profile, required)?);
+    }
+    layers.extend(env_layers()?);
+    layers.extend(cli_layers(&options.overrides)?);
+
+    // Load the default Seyuna configuration.
+    let default_configuration = Config {
+        license: None,
+        ui: Some(UI_CONFIGURATION.clone()),
+    };
+
+    // Merge every file of the chain in order over the defaults.
+    let config = layers.iter().fold(default_configuration, |config, layer| {
+        config.merge(layer.config.clone())
+    });
+
+    let mut loaded = LoadedConfig {
+        config,
+        path,
+        layers,
+        options: options.clone(),
+    };
+
+    // Refuse configurations written for another version of the CLI, theme colors whose
+    // variables clash, and semantic colors that do not stand for a theme color.
+    let diagnostics = check_loaded(&mut loaded);
+    if !diagnostics.is_empty() {
+        return Err(ValidationError { diagnostics }.into());
+    }
+
+    // Return the merged Seyuna configuration.
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::temp::TempDir;
+
+    #[test]
+    fn finds_the_nearest_configuration_walking_up() {
+        let directory = TempDir::new();
+        let root = directory.write("seyuna.json", "{}");
+        let nested = directory.write("apps/web/seyuna.toml", "");
+        std::fs::create_dir_all(directory.path().join("apps/web/src/components")).unwrap();
+        std::fs::create_dir_all(directory.path().join("apps/docs")).unwrap();
+
+        let start = directory.path().join("apps/web/src/components");
+        assert_eq!(find_config_file(&start).unwrap(), nested);
+        let start = directory.path().join("apps/docs");
+        assert_eq!(find_config_file(&start).unwrap(), root);
+        assert_eq!(find_config_file(directory.path()).unwrap(), root);
+    }
+
+    #[test]
+    fn prefers_json_within_a_directory() {
+        let directory = TempDir::new();
+        directory.write("seyuna.yaml", "");
+        let toml = directory.write("seyuna.toml", "");
+        assert_eq!(find_config_in_dir(directory.path()), Some(toml));
+
+        let json = directory.write("seyuna.json", "{}");
+        assert_eq!(find_config_file(directory.path()).unwrap(), json);
+    }
+
+    #[test]
+    fn stops_at_the_root_of_the_project() {
+        let directory = TempDir::new();
+        directory.write("seyuna.json", "{}");
+        std::fs::create_dir_all(directory.path().join("project/.git")).unwrap();
+        std::fs::create_dir_all(directory.path().join("project/src")).unwrap();
+
+        let start = directory.path().join("project/src");
+        let error = find_config_file(&start).unwrap_err();
+        assert!(
+            error.to_string().contains("Could not find seyuna."),
+            "{}",
+            error
+        );
+
+        // The root of the project is still searched
+        let config = directory.write("project/seyuna.yaml", "");
+        assert_eq!(find_config_file(&start).unwrap(), config);
+    }
+
+    #[test]
+    fn config_flag_wins_over_discovery() {
+        let directory = TempDir::new();
+        let explicit = directory.path().join("elsewhere/custom.toml");
+        assert_eq!(resolve_config_path(Some(&explicit)).unwrap(), explicit);
+    }
+
+    #[tokio::test]
+    async fn loads_the_file_given_with_the_config_flag() {
+        let directory = TempDir::new();
+        directory.write("seyuna.json", r#"{ "ui": { "name": "Discovered" } }"#);
+        let explicit = directory.write("config/custom.yaml", "ui:\n  name: Explicit\n");
+
+        let options = LoadOptions {
+            config_path: Some(explicit.clone()),
+            ..LoadOptions::default()
+        };
+        let loaded = load_seyuna_user_config(&options).await.unwrap();
+        assert_eq!(loaded.path, explicit);
+        assert_eq!(loaded.config.ui.unwrap().name, "Explicit");
+    }
+
+    #[tokio::test]
+    async fn output_dir_is_relative_to_the_configuration_file() {
+        let directory = TempDir::new();
+        let options = LoadOptions {
+            config_path: Some(directory.write(
+                "apps/web/seyuna.json",
+                r#"{ "ui": { "output_dir": "dist/css" } }"#,
+            )),
+            ..LoadOptions::default()
+        };
+        let loaded = load_seyuna_user_config(&options).await.unwrap();
+        assert_eq!(
+            loaded.output_dir().unwrap(),
+            directory.path().join("apps/web/dist/css")
+        );
+
+        // The default is next to the configuration file too
+        let options = LoadOptions {
+            config_path: Some(directory.write("apps/docs/seyuna.json", "{}")),
+            ..LoadOptions::default()
+        };
+        let loaded = load_seyuna_user_config(&options).await.unwrap();
+        assert_eq!(
+            loaded.output_dir().unwrap(),
+            directory.path().join("apps/docs/styles")
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_output_dir_is_an_error() {
+        let directory = TempDir::new();
+        let options = LoadOptions {
+            config_path: Some(directory.write("seyuna.json", r#"{ "ui": { "output_dir": " " } }"#)),
+            ..LoadOptions::default()
+        };
+        let loaded = load_seyuna_user_config(&options).await.unwrap();
+        assert!(loaded.output_dir().is_err());
+    }
 }
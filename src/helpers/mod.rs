@@ -1,2 +1,4 @@
 pub mod cli;
 pub mod fs;
+#[cfg(test)]
+pub mod temp;
@@ -0,0 +1,53 @@
+//! Temporary directories for tests touching the file system.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes the directories created by one test process.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory under the system temporary directory, removed with its content when dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory.
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "seyuna-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("Failed to create a temporary directory");
+        // Resolve symbolic links, e.g. /tmp on macOS, so paths compare with canonical ones
+        let path = path
+            .canonicalize()
+            .expect("Failed to resolve a temporary directory");
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes a file relative to the directory, creating its parent directories.
+    ///
+    /// # Returns
+    /// `PathBuf` - The path to the file.
+    pub fn write(&self, file: &str, content: &str) -> PathBuf {
+        let path = self.path.join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("Failed to create a directory");
+        }
+        std::fs::write(&path, content).expect("Failed to write a file");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,37 @@
+use anyhow::{Result, anyhow};
+use clap::ArgMatches;
+
+use crate::license::{
+    status::print_license,
+    store::{License, LicenseSource, save_activation},
+    token::{format_date, verify},
+};
+
+/// Entrypoint for the `license activate` CLI command.
+/// Verifies a license key offline and stores it for every project of the user.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+pub async fn activate(matches: &ArgMatches) -> Result<()> {
+    let key = matches.get_one::<String>("key").unwrap();
+
+    let claims = verify(key)?;
+    if let Some(expires_at) = claims.expires_at.filter(|_| claims.is_expired()) {
+        return Err(anyhow!(
+            "The license of {} expired on {} and cannot be activated",
+            claims.licensee,
+            format_date(expires_at)
+        ));
+    }
+
+    let path = save_activation(key).await?;
+    print_license(&License {
+        claims,
+        source: LicenseSource::Activation(path),
+    });
+
+    Ok(())
+}
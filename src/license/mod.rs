@@ -0,0 +1,25 @@
+use clap::ArgMatches;
+
+use crate::{
+    helpers::cli::error_text,
+    license::{activate::activate, status::status},
+};
+
+pub mod activate;
+pub mod status;
+pub mod store;
+pub mod token;
+
+pub async fn handle_license_args(matches: ArgMatches) {
+    if let Some(license_matches) = matches.subcommand_matches("license") {
+        let result = match license_matches.subcommand() {
+            Some(("activate", activate_matches)) => activate(activate_matches).await,
+            Some(("status", status_matches)) => status(status_matches).await,
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", error_text(&format!("{:#}", e)));
+            std::process::exit(1); // exit with error
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+use clap::ArgMatches;
+use owo_colors::OwoColorize;
+
+use crate::{
+    config::{types::LoadOptions, validate::display_path},
+    helpers::{
+        cli::{CHECKMARK, CROSSMARK, primary_text, secondary_text},
+        fs::{load_seyuna_user_config, resolve_config_path},
+    },
+    license::{
+        store::{License, LicenseSource, config_license_key, find_license},
+        token::format_date,
+    },
+};
+
+/// Entrypoint for the `license status` CLI command.
+/// Shows the license in use, read from the configuration or from the activated license.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - An error if the license is invalid or expired.
+pub async fn status(matches: &ArgMatches) -> Result<()> {
+    // The configuration can set the license, but is not required.
+    let config_path = matches.get_one::<PathBuf>("config").map(PathBuf::as_path);
+    let config = match resolve_config_path(config_path) {
+        Ok(_) => Some(
+            load_seyuna_user_config(&LoadOptions::from_matches(matches))
+                .await?
+                .config,
+        ),
+        Err(_) => None,
+    };
+
+    let Some(license) = find_license(config_license_key(config.as_ref()).as_deref()).await? else {
+        println!(
+            "No Seyuna license is activated. Activate one with `seyuna license activate <key>`"
+        );
+        return Ok(());
+    };
+
+    print_license(&license);
+    match license.claims.expires_at {
+        Some(expires_at) if license.claims.is_expired() => Err(anyhow!(
+            "The license expired on {}",
+            format_date(expires_at)
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Prints the details of a license.
+///
+/// # Arguments
+/// * `license` - The verified license.
+pub fn print_license(license: &License) {
+    let claims = &license.claims;
+    if claims.is_expired() {
+        println!(
+            "{} {}",
+            *CROSSMARK,
+            secondary_text(&format!("Expired license of {}", claims.licensee))
+        );
+    } else {
+        println!(
+            "{} {}",
+            *CHECKMARK,
+            primary_text(&format!("Licensed to {}", claims.licensee))
+        );
+    }
+
+    let features = if claims.features.is_empty() {
+        "none".to_string()
+    } else {
+        claims.features.join(", ")
+    };
+    let source = match &license.source {
+        LicenseSource::Config => "configuration".to_string(),
+        LicenseSource::Activation(path) => display_path(path),
+    };
+    for (label, value) in [
+        ("License", claims.id.clone()),
+        ("Features", features),
+        ("Issued", format_date(claims.issued_at)),
+        (
+            "Expires",
+            claims.expires_at.map_or("never".to_string(), format_date),
+        ),
+        ("Source", source),
+    ] {
+        println!("  {:9} {}", label.bold(), value);
+    }
+}
@@ -0,0 +1,158 @@
+//! Where licenses are stored, and finding the license that applies.
+
+use std::{env, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    config::types::Config,
+    helpers::fs::{read_file, save_file},
+    license::token::{Claims, verify},
+};
+
+/// The environment variable holding a license key, overriding the `license` field.
+pub const LICENSE_ENV: &str = "SEYUNA_LICENSE";
+
+/// Where a license key comes from.
+#[derive(Debug, Clone)]
+pub enum LicenseSource {
+    /// `SEYUNA_LICENSE`, or else the `license` field of the configuration.
+    Config,
+    /// The license activated with `seyuna license activate`.
+    Activation(PathBuf),
+}
+
+/// A verified license.
+#[derive(Debug, Clone)]
+pub struct License {
+    pub claims: Claims,
+    pub source: LicenseSource,
+}
+
+/// Returns the file the activated license is stored in, in the user's configuration directory.
+pub fn activation_file() -> Result<PathBuf> {
+    let config_dir =
+        dirs::config_dir().context("Could not find the configuration directory of the user")?;
+    Ok(config_dir.join("seyuna").join("license"))
+}
+
+/// Stores an activated license key.
+pub async fn save_activation(key: &str) -> Result<PathBuf> {
+    let path = activation_file()?;
+    save_file(&path, format!("{}\n", key.trim()).as_bytes()).await?;
+    Ok(path)
+}
+
+/// Finds and verifies the license: the one set in the configuration, or else the activated one.
+///
+/// # Arguments
+/// * `key` - The license key set in the configuration, if any.
+///
+/// # Returns
+/// `Result<Option<License>>` - The license, or `None` if there is none; an error if the
+/// license key is invalid.
+pub async fn find_license(key: Option<&str>) -> Result<Option<License>> {
+    if let Some(key) = key.filter(|key| !key.trim().is_empty()) {
+        let claims = verify(key).with_context(|| {
+            format!(
+                "The license key of the configuration or {} is invalid",
+                LICENSE_ENV
+            )
+        })?;
+        return Ok(Some(License {
+            claims,
+            source: LicenseSource::Config,
+        }));
+    }
+
+    let path = activation_file()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let key = read_file(&path).await?;
+    let claims = verify(&key).with_context(|| {
+        format!(
+            "The activated license in {:?} is invalid; activate it again",
+            path
+        )
+    })?;
+    Ok(Some(License {
+        claims,
+        source: LicenseSource::Activation(path),
+    }))
+}
+
+/// Returns the license key set for a configuration: `SEYUNA_LICENSE`, which overrides the
+/// `license` field like other environment variables override the configuration file, or else
+/// the `license` field.
+pub fn config_license_key(config: Option<&Config>) -> Option<String> {
+    license_key(env::var(LICENSE_ENV).ok(), config)
+}
+
+/// Returns the license key of the `SEYUNA_LICENSE` value if set, or else of the configuration.
+fn license_key(variable: Option<String>, config: Option<&Config>) -> Option<String> {
+    variable
+        .filter(|key| !key.trim().is_empty())
+        .or_else(|| config.and_then(|config| config.license.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helpers::temp::TempDir, ui::compile::compile};
+    use clap::{Arg, ArgAction, Command, value_parser};
+
+    fn config(license: Option<&str>) -> Config {
+        Config {
+            license: license.map(str::to_string),
+            ui: None,
+        }
+    }
+
+    #[test]
+    fn license_variable_overrides_the_configuration() {
+        let variable = || Some("from-env".to_string());
+        let config = config(Some("from-config"));
+
+        assert_eq!(
+            license_key(variable(), Some(&config)).as_deref(),
+            Some("from-env")
+        );
+        assert_eq!(license_key(variable(), None).as_deref(), Some("from-env"));
+        assert_eq!(
+            license_key(None, Some(&config)).as_deref(),
+            Some("from-config")
+        );
+        assert_eq!(
+            license_key(Some(" ".to_string()), Some(&config)).as_deref(),
+            Some("from-config")
+        );
+    }
+
+    #[tokio::test]
+    async fn workspaces_compile_without_a_license() {
+        let directory = TempDir::new();
+        let root = directory.write(
+            "seyuna.json",
+            r#"{ "workspace": { "members": ["apps/*"] } }"#,
+        );
+        directory.write("apps/a/seyuna.json", "{}");
+        directory.write("apps/b/seyuna.json", "{}");
+
+        let matches = Command::new("seyuna")
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(Arg::new("profile").long("profile"))
+            .arg(Arg::new("set").long("set").action(ArgAction::Append))
+            .arg(Arg::new("watch").long("watch").action(ArgAction::SetTrue))
+            .get_matches_from(["seyuna", "--config", root.to_str().unwrap()]);
+
+        let loaded = compile(&matches).await.unwrap();
+        assert_eq!(loaded.config.license, None);
+        assert!(directory.path().join("apps/a/styles").exists());
+        assert!(directory.path().join("apps/b/styles").exists());
+    }
+}
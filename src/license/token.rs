@@ -0,0 +1,257 @@
+//! Signed license tokens, verified offline.
+//!
+//! A license key is `<payload>.<signature>`: the base64url-encoded JSON [`Claims`], followed
+//! by the base64url-encoded ed25519 signature of the encoded payload.
+//!
+//! The public key is embedded at build time from `SEYUNA_LICENSE_PUBLIC_KEY`, which the release
+//! workflow sets to the production key. Debug builds without it use the development key;
+//! release builds without it, e.g. from `cargo install`, build and run normally but cannot
+//! verify licenses, and say so when one is needed.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// The public key verifying license signatures, base64url-encoded.
+/// Release builds never fall back to the development key, so a binary built without the
+/// production key does not accept licenses signed with the development key.
+#[cfg(not(debug_assertions))]
+const PUBLIC_KEY: Option<&str> = option_env!("SEYUNA_LICENSE_PUBLIC_KEY");
+
+/// The public key verifying license signatures, base64url-encoded.
+/// Debug builds fall back to the development key when no key is set at build time.
+#[cfg(debug_assertions)]
+const PUBLIC_KEY: Option<&str> = match option_env!("SEYUNA_LICENSE_PUBLIC_KEY") {
+    Some(key) => Some(key),
+    None => Some("_mMiGQQmmCdSPlH54tOFpeCeiJP5U-ybr6eN5mpowio"),
+};
+
+/// The claims of a license, signed by Seyuna.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// The license identifier.
+    pub id: String,
+    /// Who the license is issued to.
+    pub licensee: String,
+    /// The premium features the license includes, or `*` for all of them.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// When the license was issued, in seconds since the Unix epoch.
+    pub issued_at: u64,
+    /// When the license expires, in seconds since the Unix epoch; never if missing.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl Claims {
+    /// Returns whether the license has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= now())
+    }
+}
+
+/// Verifies the signature of a license key with the embedded public key and reads its claims.
+/// Expiry is not checked, so expired licenses can still be shown.
+///
+/// # Arguments
+/// * `key` - The license key.
+///
+/// # Returns
+/// `Result<Claims>` - The claims, if the key is signed by Seyuna.
+pub fn verify(key: &str) -> Result<Claims> {
+    verify_with(key, &public_key()?)
+}
+
+/// Verifies the signature of a license key with `public_key` and reads its claims.
+///
+/// # Arguments
+/// * `key` - The license key.
+/// * `public_key` - The key verifying the signature.
+///
+/// # Returns
+/// `Result<Claims>` - The claims, if the key is signed by the matching private key.
+fn verify_with(key: &str, public_key: &VerifyingKey) -> Result<Claims> {
+    let (payload, signature) = key
+        .trim()
+        .split_once('.')
+        .context("The license key is malformed")?;
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .ok()
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .context("The license key is malformed")?;
+    public_key
+        .verify_strict(payload.as_bytes(), &signature)
+        .map_err(|_| anyhow!("The license key is not signed by Seyuna"))?;
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .context("The license key is malformed")?;
+    serde_json::from_slice(&payload).context("The license key contains invalid claims")
+}
+
+/// Decodes the public key embedded in the binary.
+fn public_key() -> Result<VerifyingKey> {
+    let key = PUBLIC_KEY.filter(|key| !key.is_empty()).context(
+        "This build of Seyuna cannot verify licenses, as it was built without \
+        SEYUNA_LICENSE_PUBLIC_KEY. Install an official release to use a license",
+    )?;
+    let bytes: [u8; 32] = URL_SAFE_NO_PAD
+        .decode(key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .context("The embedded license public key is invalid")?;
+    VerifyingKey::from_bytes(&bytes).context("The embedded license public key is invalid")
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Formats a time in seconds since the Unix epoch as a `YYYY-MM-DD` date, in UTC.
+pub fn format_date(timestamp: u64) -> String {
+    // Days to civil date, from Howard Hinnant's `civil_from_days`.
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn sign(signing_key: &SigningKey, payload: &str) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(payload);
+        let signature = signing_key.sign(payload.as_bytes());
+        format!(
+            "{}.{}",
+            payload,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    fn claims_json(features: &str, expires_at: &str) -> String {
+        format!(
+            r#"{{"id":"lic_1","licensee":"Acme","features":{},"issued_at":0,"expires_at":{}}}"#,
+            features, expires_at
+        )
+    }
+
+    #[test]
+    fn valid_tokens_are_verified() {
+        let key = signing_key();
+        let token = sign(&key, &claims_json(r#"["audit"]"#, "null"));
+
+        let claims = verify_with(&token, &key.verifying_key()).unwrap();
+        assert_eq!(claims.id, "lic_1");
+        assert_eq!(claims.licensee, "Acme");
+        assert_eq!(claims.features, ["audit"]);
+        assert!(!claims.is_expired());
+
+        // Surrounding whitespace, e.g. from a pasted key, is ignored
+        let padded = format!("  {}\n", token);
+        assert!(verify_with(&padded, &key.verifying_key()).is_ok());
+    }
+
+    #[test]
+    fn tampered_payloads_are_rejected() {
+        let key = signing_key();
+        let token = sign(&key, &claims_json(r#"["audit"]"#, "null"));
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = URL_SAFE_NO_PAD.encode(claims_json(r#"["*"]"#, "null"));
+
+        let error =
+            verify_with(&format!("{}.{}", forged, signature), &key.verifying_key()).unwrap_err();
+        assert_eq!(error.to_string(), "The license key is not signed by Seyuna");
+    }
+
+    #[test]
+    fn tokens_signed_by_another_key_are_rejected() {
+        let token = sign(
+            &SigningKey::from_bytes(&[8; 32]),
+            &claims_json(r#"["*"]"#, "null"),
+        );
+
+        let error = verify_with(&token, &signing_key().verifying_key()).unwrap_err();
+        assert_eq!(error.to_string(), "The license key is not signed by Seyuna");
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let key = signing_key();
+        let token = sign(&key, &claims_json("[]", "null"));
+        let (payload, signature) = token.split_once('.').unwrap();
+        let short_signature = URL_SAFE_NO_PAD.encode([0; 63]);
+
+        for malformed in [
+            String::new(),
+            "no-separator".to_string(),
+            format!("{}.not base64!", payload),
+            format!("{}.{}", payload, short_signature),
+            format!("{}.{}", payload, &signature[..signature.len() - 4]),
+        ] {
+            let error = verify_with(&malformed, &key.verifying_key()).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "The license key is malformed",
+                "{:?}",
+                malformed
+            );
+        }
+    }
+
+    #[test]
+    fn signed_payloads_with_invalid_claims_are_rejected() {
+        let key = signing_key();
+        let token = sign(&key, r#"{"id":"lic_1"}"#);
+
+        let error = verify_with(&token, &key.verifying_key()).unwrap_err();
+        assert_eq!(error.to_string(), "The license key contains invalid claims");
+    }
+
+    #[test]
+    fn expired_tokens_are_verified_but_expired() {
+        let key = signing_key();
+        let token = sign(&key, &claims_json(r#"["audit"]"#, "86400"));
+
+        let claims = verify_with(&token, &key.verifying_key()).unwrap();
+        assert!(claims.is_expired());
+        assert_eq!(format_date(claims.expires_at.unwrap()), "1970-01-02");
+    }
+
+    #[test]
+    fn feature_claims_default_to_none() {
+        let key = signing_key();
+        let token = sign(&key, r#"{"id":"lic_2","licensee":"Acme","issued_at":0}"#);
+
+        let claims = verify_with(&token, &key.verifying_key()).unwrap();
+        assert!(claims.features.is_empty());
+        assert_eq!(claims.expires_at, None);
+        assert!(!claims.is_expired());
+    }
+}
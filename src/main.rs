@@ -1,21 +1,25 @@
 mod config;
 mod helpers;
+mod license;
 mod schema;
 mod ui;
 
+use std::path::PathBuf;
+
 use clap::{
     Arg, ArgAction, Command,
     builder::{
         Styles,
         styling::{Color, RgbColor, Style},
     },
-    crate_version,
+    crate_version, value_parser,
 };
 use owo_colors::OwoColorize;
 
 use crate::{
     config::handle_config_args,
     helpers::cli::{primary_text, secondary_text},
+    license::handle_license_args,
     schema::handle_schema_args,
     ui::handle_ui_args,
 };
@@ -24,8 +28,9 @@ use crate::{
 ///
 /// # Subcommands
 /// - config
+/// - license
 /// - ui
-/// - generate-json-schema (hidden)
+/// - schema
 #[tokio::main]
 async fn main() {
     // Define custom CLI styles for better visual formatting
@@ -50,15 +55,163 @@ async fn main() {
                 .bold()
                 .to_string(),
         )
+        // Global option to point at a specific configuration file
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("PATH")
+                .help("Path to the Seyuna configuration file (defaults to the nearest seyuna.json)")
+                .value_parser(value_parser!(PathBuf))
+                .global(true),
+        )
+        // Global option to select a configuration profile
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .help("Configuration profile to apply, e.g. production (defaults to $SEYUNA_PROFILE)")
+                .global(true),
+        )
+        // Global option to override configuration values, e.g. `--set ui.mode=dark`
+        .arg(
+            Arg::new("set")
+                .long("set")
+                .value_name("PATH=VALUE")
+                .help("Override a configuration value, e.g. --set ui.mode=dark (repeatable)")
+                .action(ArgAction::Append)
+                .global(true),
+        )
         // `config` subcommand
         .subcommand(
-            Command::new("config").about("Configure Seyuna").arg(
-                Arg::new("init")
-                    .short('i')
-                    .long("init")
-                    .help("Initialize Seyuna configuration")
-                    .action(ArgAction::SetTrue),
-            ),
+            Command::new("config")
+                .about("Configure Seyuna")
+                .subcommand(
+                    Command::new("init")
+                        // Keep `seyuna config --init` working
+                        .short_flag('i')
+                        .long_flag("init")
+                        .about("Initialize Seyuna configuration")
+                        .arg(
+                            Arg::new("format")
+                                .short('f')
+                                .long("format")
+                                .help("File format of the configuration")
+                                .value_parser(["json", "jsonc", "toml", "yaml"]),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .help("Overwrite an existing configuration file")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("yes")
+                                .short('y')
+                                .long("yes")
+                                .help("Skip the questions and use the default answers")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("validate")
+                        .about("Validate the Seyuna configuration")
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .help("Output format of the diagnostics")
+                                .value_parser(["text", "json"])
+                                .default_value("text"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the Seyuna configuration")
+                        .after_help(
+                            "With --resolved, values are merged in this order, \
+                            each overriding the ones before it:\n  \
+                            1. The built-in defaults\n  \
+                            2. Extended configurations, in `extends` order\n  \
+                            3. The configuration file\n  \
+                            4. The selected profile, e.g. --profile production or SEYUNA_PROFILE=production\n  \
+                            5. SEYUNA_UI__* environment variables, e.g. SEYUNA_UI__OUTPUT_DIR=dist/css\n  \
+                            6. --set flags, e.g. --set ui.mode=dark\n\n\
+                            Environment variable names are lowercased, so keys with uppercase letters,\n\
+                            e.g. a color named brandBlue, can only be overridden with --set.",
+                        )
+                        .arg(
+                            Arg::new("resolved")
+                                .long("resolved")
+                                .help("Print the effective configuration after merging every layer over the defaults")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("annotate")
+                                .long("annotate")
+                                .help("Show where each value comes from")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .help("Output format")
+                                .value_parser(["table", "json", "toml"])
+                                .default_value("table"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("get")
+                        .about("Print a configuration value")
+                        .arg(
+                            Arg::new("path")
+                                .help("Dotted path of the value, e.g. ui.theme.colors.brand")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("resolved")
+                                .long("resolved")
+                                .help("Read the value after merging defaults and extended files")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Set a configuration value")
+                        .arg(
+                            Arg::new("path")
+                                .help("Dotted path of the value, e.g. ui.theme.dark.lightness")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("value")
+                                .help("The new value, read as JSON if possible and as a string otherwise")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("string")
+                                .long("string")
+                                .help("Always store the value as a string")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("unset")
+                        .about("Remove a configuration value")
+                        .arg(
+                            Arg::new("path")
+                                .help("Dotted path of the value, e.g. ui.mode")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Upgrade the configuration file to the current version")
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Show the changes as a diff without saving them")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
         )
         // `ui` subcommand
         .subcommand(
@@ -77,18 +230,82 @@ async fn main() {
                         .long("watch")
                         .help("Watch Seyuna UI styles")
                         .action(ArgAction::SetTrue),
+                )
+                .subcommand(
+                    Command::new("audit")
+                        .about("Check the contrast of the theme colors against WCAG 2 and APCA")
+                        .arg(
+                            Arg::new("level")
+                                .long("level")
+                                .help("WCAG 2 level every color pair must meet")
+                                .value_parser(["aa", "aaa"])
+                                .default_value("aa"),
+                        )
+                        .arg(
+                            Arg::new("large-text")
+                                .long("large-text")
+                                .help("Use the ratios for large text: 3:1 for AA and 4.5:1 for AAA")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("min-lc")
+                                .long("min-lc")
+                                .value_name("LC")
+                                .help("Also require an APCA contrast of at least LC, e.g. 60")
+                                .value_parser(value_parser!(f64)),
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .help("Output format of the report")
+                                .value_parser(["text", "json"])
+                                .default_value("text"),
+                        ),
+                ),
+        )
+        // `license` subcommand
+        .subcommand(
+            Command::new("license")
+                .about(secondary_text("Manage the Seyuna license"))
+                .subcommand(
+                    Command::new("activate")
+                        .about("Verify a license key and store it for this user")
+                        .arg(
+                            Arg::new("key")
+                                .help("The license key")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    Command::new("status")
+                        .about("Show the license in use, from the configuration or the activated license"),
                 ),
         )
-        // Hidden subcommand for generating the JSON schema
+        // `schema` subcommand
         .subcommand(
-            Command::new("generate-json-schema")
-                .about(secondary_text("Generates the JSON schema for the cli"))
-                .hide(true),
+            Command::new("schema")
+                .about(secondary_text("Generate the JSON schema of the configuration file"))
+                .alias("generate-json-schema")
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .value_name("PATH")
+                        .help("File or directory to write the schema to (defaults to schema/v-<version>.schema.json)")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("stdout")
+                        .long("stdout")
+                        .help("Print the schema instead of writing it to a file")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("out"),
+                ),
         )
         .get_matches();
 
     // Dispatch to the appropriate handler based on the subcommand
     handle_schema_args(matches.clone()).await;
     handle_config_args(matches.clone()).await;
+    handle_license_args(matches.clone()).await;
     handle_ui_args(matches.clone()).await;
 }
@@ -1,41 +1,193 @@
-use std::{
-    fs::{self, File},
-    io::Write,
-    path::Path,
-};
+use std::path::{Path, PathBuf};
 
+use anyhow::Result;
 use clap::ArgMatches;
-use schemars::schema_for;
+use schemars::{Schema, schema_for};
+
+use crate::{
+    config::{types::PartialConfig, validate::display_path},
+    helpers::{
+        cli::{CHECKMARK, error_text, primary_text},
+        fs::save_file,
+    },
+};
+
+/// The directory the JSON schema is written to, relative to the current directory or to the
+/// configuration file referencing it.
+const SCHEMA_DIR: &str = "schema";
 
-use crate::config::types::Config;
+/// Returns the file name of the JSON schema for a version, e.g. `v-1.2.3.schema.json`.
+fn schema_file_name(version: &str) -> String {
+    format!("v-{}.schema.json", version)
+}
+
+/// Generates the JSON schema for configuration files, where every field is optional.
+pub fn config_schema() -> Schema {
+    schema_for!(PartialConfig)
+}
+
+/// Returns the `$schema` reference, relative to a configuration file, to the JSON schema that
+/// [`write_config_schema`] writes next to it, e.g. `./schema/v-1.2.3.schema.json`.
+pub fn schema_reference() -> String {
+    format!(
+        "./{}/{}",
+        SCHEMA_DIR,
+        schema_file_name(env!("CARGO_PKG_VERSION"))
+    )
+}
 
 pub async fn handle_schema_args(matches: ArgMatches) {
-    // Check if the 'generate-json-schema' subcommand was invoked
-    if let Some(_args) = matches.subcommand_matches("generate-json-schema") {
-        // Get the current crate version from the environment
-        let version = env!("CARGO_PKG_VERSION");
+    // Check if the 'schema' subcommand was invoked
+    if let Some(schema_matches) = matches.subcommand_matches("schema")
+        && let Err(e) = write_schema(schema_matches).await
+    {
+        eprintln!("{}", error_text(&format!("{:#}", e)));
+        std::process::exit(1); // exit with error
+    }
+}
+
+/// Writes the JSON schema next to a configuration file, where its `$schema` reference points.
+///
+/// # Arguments
+/// * `config_path` - The path of the configuration file.
+///
+/// # Returns
+/// `Result<PathBuf>` - The path of the written schema, e.g. `schema/v-1.2.3.schema.json`.
+pub async fn write_config_schema(config_path: &Path) -> Result<PathBuf> {
+    let json = serde_json::to_string_pretty(&config_schema())?;
+    let dest_path = config_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(SCHEMA_DIR)
+        .join(schema_file_name(env!("CARGO_PKG_VERSION")));
+    save_file(&dest_path, json.as_bytes()).await?;
+    Ok(dest_path)
+}
 
-        // Define the output directory for the schema files
-        let dist_dir = Path::new("schema");
+/// Entrypoint for the `schema` CLI command.
+/// Writes the JSON schema to `schema/v-<version>.schema.json`, to `--out`, or to stdout.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+async fn write_schema(matches: &ArgMatches) -> Result<()> {
+    // Serialize the schema into a pretty-printed JSON string
+    let json = serde_json::to_string_pretty(&config_schema())?;
 
-        // Create the directory if it doesn't already exist
-        fs::create_dir_all(dist_dir).unwrap();
+    if matches.get_flag("stdout") {
+        println!("{}", json);
+        return Ok(());
+    }
+
+    // A directory given as --out gets the versioned file name.
+    let file_name = schema_file_name(env!("CARGO_PKG_VERSION"));
+    let is_dir = |out: &Path| out.is_dir() || out.to_string_lossy().ends_with(['/', '\\']);
+    let dest_path = match matches.get_one::<PathBuf>("out") {
+        Some(out) if is_dir(out) => out.join(file_name),
+        Some(out) => out.clone(),
+        None => Path::new(SCHEMA_DIR).join(file_name),
+    };
 
-        // Generate the JSON schema for the Config struct
-        let schema = schema_for!(Config);
+    // Write the JSON schema to the file
+    save_file(&dest_path, json.as_bytes()).await?;
 
-        // Serialize the schema into a pretty-printed JSON string
-        let json = serde_json::to_string_pretty(&schema).unwrap();
+    println!(
+        "{} {}",
+        *CHECKMARK,
+        primary_text(&format!(
+            "JSON schema written to {}",
+            display_path(&dest_path)
+        ))
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::default::UI_CONFIGURATION;
+    use serde_json::{Value, json};
+
+    /// Returns a property of a definition of the configuration schema.
+    fn property(definition: &str, name: &str) -> Value {
+        let schema = config_schema().to_value();
+        schema["$defs"][definition]["properties"][name].clone()
+    }
 
-        // Construct the filename using the current version, e.g., "v-1.2.3.schema.json"
-        let file_name = format!("v-{}.schema.json", version);
-        let dest_path = dist_dir.join(file_name);
+    #[test]
+    fn hues_are_limited_to_a_full_turn() {
+        let schema = config_schema().to_value();
+        let hue = &schema["$defs"]["ThemeColor"]["anyOf"][0];
+        assert_eq!(hue["type"], "number");
+        assert_eq!((&hue["minimum"], &hue["maximum"]), (&json!(0), &json!(360)));
 
-        // Write the JSON schema to the file
-        let mut file = File::create(&dest_path).unwrap();
-        file.write_all(json.as_bytes()).unwrap();
+        for (definition, name) in [("CustomColor", "hue"), ("PartialColor", "hue")] {
+            let hue = property(definition, name);
+            assert_eq!((&hue["minimum"], &hue["maximum"]), (&json!(0), &json!(360)));
+        }
+    }
+
+    #[test]
+    fn values_have_their_ranges() {
+        for (definition, name, minimum, maximum) in [
+            ("PartialPalette", "lightness", json!(0), json!(1)),
+            ("PartialPalette", "chroma", json!(0), Value::Null),
+            ("PartialOnColors", "contrast", json!(0), json!(21)),
+            ("PartialNeutral", "surface_step", json!(0), json!(0.2)),
+            ("PartialScale", "steps", json!(0), json!(11)),
+        ] {
+            let value = property(definition, name);
+            assert_eq!(value["minimum"], minimum, "{}.{}", definition, name);
+            assert_eq!(value["maximum"], maximum, "{}.{}", definition, name);
+        }
+    }
+
+    #[test]
+    fn defaults_are_those_of_the_cli() {
+        let output_dir = property("PartialUI", "output_dir");
+        assert_eq!(output_dir["default"], json!(UI_CONFIGURATION.output_dir));
+        assert_eq!(output_dir["examples"], json!(["src/styles"]));
+
+        assert_eq!(
+            property("PartialUI", "name")["default"],
+            json!(UI_CONFIGURATION.name)
+        );
+        assert_eq!(property("PartialUI", "mode")["default"], "system");
+        assert_eq!(
+            property("PartialScale", "steps")["default"],
+            json!(UI_CONFIGURATION.theme.scale.steps)
+        );
+        assert_eq!(
+            property("PartialOnColors", "contrast")["default"],
+            json!(UI_CONFIGURATION.theme.on_colors.contrast)
+        );
+    }
 
-        // Print a cargo warning to indicate where the schema was written
-        println!("cargo:warning=JSON schema written to {:?}", dest_path);
+    #[test]
+    fn examples_are_valid_values() {
+        let schema = config_schema().to_value();
+        let mut values = vec![&schema];
+        while let Some(value) = values.pop() {
+            match value {
+                Value::Object(object) => {
+                    if let Some(Value::Array(examples)) = object.get("examples") {
+                        for example in examples {
+                            if let Some(example) = example.as_f64() {
+                                let minimum = object.get("minimum").and_then(Value::as_f64);
+                                let maximum = object.get("maximum").and_then(Value::as_f64);
+                                assert!(minimum.is_none_or(|minimum| example >= minimum));
+                                assert!(maximum.is_none_or(|maximum| example <= maximum));
+                            }
+                        }
+                    }
+                    values.extend(object.values());
+                }
+                Value::Array(array) => values.extend(array),
+                _ => {}
+            }
+        }
     }
 }
@@ -0,0 +1,418 @@
+//! Contrast checks of the theme colors against the WCAG 2 and APCA guidelines.
+
+use std::fmt;
+
+use anyhow::{Context, Result, anyhow};
+use clap::ArgMatches;
+use serde::Serialize;
+
+use crate::{
+    config::types::LoadOptions,
+    helpers::{
+        cli::{CHECKMARK, CROSSMARK, primary_text},
+        fs::load_seyuna_user_config,
+    },
+    ui::{
+        color::{Gamut, Oklch, apca_contrast, contrast_ratio},
+        compile::print_diagnostics,
+        types::{Color, Mode, Palette, UI},
+    },
+};
+
+/// The WCAG 2 conformance level every color pair must meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Level {
+    Aa,
+    Aaa,
+}
+
+impl Level {
+    /// Returns the lowest contrast ratio meeting the level.
+    ///
+    /// # Arguments
+    /// * `large_text` - Whether the text is large, i.e. at least 18pt, or 14pt and bold.
+    pub fn min_ratio(self, large_text: bool) -> f64 {
+        match (self, large_text) {
+            (Self::Aa, false) => 4.5,
+            (Self::Aa, true) => 3.0,
+            (Self::Aaa, false) => 7.0,
+            (Self::Aaa, true) => 4.5,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Aa => write!(formatter, "AA"),
+            Self::Aaa => write!(formatter, "AAA"),
+        }
+    }
+}
+
+/// What the color pairs are checked against.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Requirement {
+    pub level: Level,
+    pub large_text: bool,
+    /// The lowest APCA contrast allowed, in absolute value; not checked if missing.
+    pub min_lc: Option<f64>,
+}
+
+/// The contrast of a color shown on a background in one mode.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContrastCheck {
+    pub mode: &'static str,
+    /// The name of the color, e.g. `text` or a theme color.
+    pub foreground: String,
+    pub background: String,
+    /// The colors as shown on sRGB displays.
+    pub foreground_color: String,
+    pub background_color: String,
+    /// The WCAG 2 contrast ratio, from `1` to `21`, rounded to 2 decimals.
+    pub ratio: f64,
+    pub aa: bool,
+    pub aaa: bool,
+    /// The APCA lightness contrast, negative for light text on a dark background, rounded to
+    /// 1 decimal.
+    pub apca: f64,
+    /// Whether the pair meets the requirement.
+    pub passes: bool,
+}
+
+/// Checks the contrast of the text and of every theme color on the background of each mode,
+/// and of every on-color on its theme color.
+///
+/// # Arguments
+/// * `ui` - The UI configuration.
+/// * `requirement` - What the pairs are checked against.
+///
+/// # Returns
+/// `Vec<ContrastCheck>` - The checks of the light mode, then of the dark mode.
+pub fn contrast_checks(ui: &UI, requirement: Requirement) -> Vec<ContrastCheck> {
+    let mut checks = Vec::new();
+    for (mode, mode_name, palette) in [
+        (Mode::Light, "light", &ui.theme.light),
+        (Mode::Dark, "dark", &ui.theme.dark),
+    ] {
+        let background = color(&palette.background);
+        let check = |foreground: &str, color: Oklch| {
+            contrast_check(
+                mode_name,
+                (foreground, color),
+                ("background", background),
+                requirement,
+            )
+        };
+
+        checks.push(check("text", color(&palette.text)));
+        for (name, theme_color) in &ui.theme.colors {
+            checks.push(check(name, theme_color.color(mode, palette)));
+        }
+
+        // The on-colors, on their theme colors
+        if !ui.theme.on_colors.enabled() {
+            continue;
+        }
+        for (name, theme_color) in &ui.theme.colors {
+            let color = theme_color.color(mode, palette);
+            checks.push(contrast_check(
+                mode_name,
+                (
+                    &format!("{}-on", name),
+                    ui.theme.on_colors.foreground(color, Gamut::Srgb),
+                ),
+                (name, color),
+                requirement,
+            ));
+        }
+    }
+    checks
+}
+
+/// Checks the contrast of a named color on a named background.
+fn contrast_check(
+    mode: &'static str,
+    (foreground, color): (&str, Oklch),
+    (background_name, background): (&str, Oklch),
+    requirement: Requirement,
+) -> ContrastCheck {
+    let ratio = contrast_ratio(color, background, Gamut::Srgb);
+    let apca = apca_contrast(color, background);
+
+    // Compare the exact values, so a ratio of 4.495 fails AA even though it is shown as 4.50
+    ContrastCheck {
+        mode,
+        foreground: foreground.to_string(),
+        background: background_name.to_string(),
+        foreground_color: color.to_hex(),
+        background_color: background.to_hex(),
+        ratio: (ratio * 100.0).round() / 100.0,
+        aa: ratio >= Level::Aa.min_ratio(requirement.large_text),
+        aaa: ratio >= Level::Aaa.min_ratio(requirement.large_text),
+        apca: (apca * 10.0).round() / 10.0,
+        passes: ratio >= requirement.level.min_ratio(requirement.large_text)
+            && requirement.min_lc.is_none_or(|min_lc| apca.abs() >= min_lc),
+    }
+}
+
+/// Returns a palette color as an OKLCH color.
+fn color(color: &Color) -> Oklch {
+    Oklch::from_numbers(&color.lightness, &color.chroma, &color.hue)
+}
+
+/// Entrypoint for the `ui audit` CLI command.
+/// Checks the contrast of the theme and fails if any color pair does not meet the requirement.
+///
+/// # Arguments
+/// * `matches` - CLI argument matches.
+///
+/// # Returns
+/// * `Result<()>` - No value; an error if any color pair fails.
+pub async fn audit(matches: &ArgMatches) -> Result<()> {
+    let json = matches.get_one::<String>("format").map(String::as_str) == Some("json");
+    let requirement = Requirement {
+        level: match matches.get_one::<String>("level").map(String::as_str) {
+            Some("aaa") => Level::Aaa,
+            _ => Level::Aa,
+        },
+        large_text: matches.get_flag("large-text"),
+        min_lc: matches.get_one::<f64>("min-lc").copied(),
+    };
+
+    let loaded = load_seyuna_user_config(&LoadOptions::from_matches(matches)).await?;
+    if !json {
+        print_diagnostics(&loaded);
+    }
+    let ui = loaded
+        .config
+        .ui
+        .as_ref()
+        .context("UI configuration missing in Seyuna config")?;
+
+    let checks = contrast_checks(ui, requirement);
+    let failed = checks.iter().filter(|check| !check.passes).count();
+
+    if json {
+        let report = serde_json::json!({
+            "passed": failed == 0,
+            "failed": failed,
+            "requirement": requirement,
+            "checks": checks,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_checks(&checks, &ui.theme.light, &ui.theme.dark);
+    }
+
+    let level = format!(
+        "WCAG {}{}{}",
+        requirement.level,
+        if requirement.large_text {
+            " for large text"
+        } else {
+            ""
+        },
+        requirement
+            .min_lc
+            .map_or(String::new(), |min_lc| format!(" and APCA Lc {}", min_lc))
+    );
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} color pairs do not meet {}",
+            failed,
+            checks.len(),
+            level
+        ));
+    }
+    if !json {
+        println!(
+            "{} {}",
+            *CHECKMARK,
+            primary_text(&format!("Every color pair meets {}", level))
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the checks as a table per mode.
+fn print_checks(checks: &[ContrastCheck], light: &Palette, dark: &Palette) {
+    let label = |check: &ContrastCheck| format!("{} on {}", check.foreground, check.background);
+    let width = checks
+        .iter()
+        .map(|check| label(check).len())
+        .max()
+        .unwrap_or_default();
+    let mark = |passes: bool| if passes { &*CHECKMARK } else { &*CROSSMARK };
+
+    for (mode, palette) in [("light", light), ("dark", dark)] {
+        println!(
+            "{} (background {})",
+            primary_text(&format!("{}{} mode", mode[..1].to_uppercase(), &mode[1..])),
+            color(&palette.background).to_hex()
+        );
+        for check in checks.iter().filter(|check| check.mode == mode) {
+            println!(
+                "  {} {:<width$}  {:>5.2}:1  AA {}  AAA {}  Lc {:>6.1}",
+                mark(check.passes),
+                label(check),
+                check.ratio,
+                mark(check.aa),
+                mark(check.aaa),
+                check.apca,
+                width = width
+            );
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::{default::UI_CONFIGURATION, types::PartialUI};
+    use serde_json::{Value, json};
+
+    /// Merges a partial UI configuration over the defaults.
+    fn ui(partial: Value) -> UI {
+        UI_CONFIGURATION
+            .clone()
+            .merge(serde_json::from_value::<PartialUI>(partial).unwrap())
+    }
+
+    /// Returns the OKLCH lightness of the sRGB gray with the channel, e.g. `0x77` for `#777777`.
+    fn gray(channel: u8) -> f64 {
+        let channel = f64::from(channel) / 255.0;
+        ((channel + 0.055) / 1.055).powf(2.4).cbrt()
+    }
+
+    /// A theme without colors, whose light mode shows text of a gray on white.
+    fn gray_text(channel: u8) -> UI {
+        ui(json!({ "theme": {
+            "colors": { "$replace": true },
+            "light": { "text": { "lightness": gray(channel) } },
+        } }))
+    }
+
+    fn requirement(level: Level, large_text: bool, min_lc: Option<f64>) -> Requirement {
+        Requirement {
+            level,
+            large_text,
+            min_lc,
+        }
+    }
+
+    /// Returns the check of a foreground on a background in a mode.
+    fn find<'a>(
+        checks: &'a [ContrastCheck],
+        mode: &str,
+        foreground: &str,
+        background: &str,
+    ) -> &'a ContrastCheck {
+        checks
+            .iter()
+            .find(|check| {
+                check.mode == mode
+                    && check.foreground == foreground
+                    && check.background == background
+            })
+            .unwrap_or_else(|| panic!("no check of {} on {} in {}", foreground, background, mode))
+    }
+
+    #[test]
+    fn accessible_theme_passes() {
+        let ui = ui(json!({ "theme": {
+            "colors": {
+                "$replace": true,
+                "ink": {
+                    "hue": 250,
+                    "light": { "chroma": 0.1, "lightness": 0.4 },
+                    "dark": { "chroma": 0.1, "lightness": 0.85 },
+                },
+            },
+            "on_colors": { "contrast": 4.5 },
+        } }));
+
+        let checks = contrast_checks(&ui, requirement(Level::Aa, false, None));
+        assert_eq!(checks.len(), 6);
+        assert!(checks.iter().all(|check| check.passes), "{:#?}", checks);
+        assert_eq!(find(&checks, "light", "text", "background").ratio, 21.0);
+    }
+
+    #[test]
+    fn just_below_aa_passes_only_for_large_text() {
+        let ui = gray_text(0x77);
+
+        let checks = contrast_checks(&ui, requirement(Level::Aa, false, None));
+        let text = find(&checks, "light", "text", "background");
+        assert_eq!(text.foreground_color, "#777777");
+        assert!(text.ratio < 4.5);
+        assert!(!text.aa && !text.passes);
+        // The dark mode is untouched
+        assert!(find(&checks, "dark", "text", "background").passes);
+
+        let checks = contrast_checks(&ui, requirement(Level::Aa, true, None));
+        let text = find(&checks, "light", "text", "background");
+        assert!(text.aa && text.passes);
+    }
+
+    #[test]
+    fn aaa_needs_more_contrast() {
+        // About 5.7:1 on white
+        let ui = gray_text(0x66);
+
+        let aa = contrast_checks(&ui, requirement(Level::Aa, false, None));
+        let text = find(&aa, "light", "text", "background");
+        assert!(text.aa && !text.aaa && text.passes);
+
+        let aaa = contrast_checks(&ui, requirement(Level::Aaa, false, None));
+        assert!(!find(&aaa, "light", "text", "background").passes);
+
+        let aaa_large = contrast_checks(&ui, requirement(Level::Aaa, true, None));
+        let text = find(&aaa_large, "light", "text", "background");
+        assert!(text.aaa && text.passes);
+    }
+
+    #[test]
+    fn min_lc_gates_on_the_absolute_apca_contrast() {
+        let ui = gray_text(0x88);
+
+        let checks = contrast_checks(&ui, requirement(Level::Aa, true, Some(60.0)));
+        let text = find(&checks, "light", "text", "background");
+        assert_eq!(text.apca, 63.1);
+        assert!(text.passes);
+
+        let checks = contrast_checks(&ui, requirement(Level::Aa, true, Some(75.0)));
+        let text = find(&checks, "light", "text", "background");
+        assert!(text.aa && !text.passes);
+
+        // Light text on a dark background has a negative Lc, which counts by its size
+        let dark = find(&checks, "dark", "text", "background");
+        assert!(dark.apca < -100.0);
+        assert!(dark.passes);
+    }
+
+    #[test]
+    fn every_theme_color_has_an_on_color_check_in_both_modes() {
+        let ui = ui(json!({ "theme": { "on_colors": { "contrast": 4.5 } } }));
+        let checks = contrast_checks(&ui, requirement(Level::Aa, false, None));
+        let colors = ui.theme.colors.len();
+        assert_eq!(checks.len(), 2 * (1 + 2 * colors));
+
+        for mode in ["light", "dark"] {
+            for name in ui.theme.colors.keys() {
+                let on_color = find(&checks, mode, &format!("{}-on", name), name);
+                let color = find(&checks, mode, name, "background");
+                assert_eq!(on_color.background_color, color.foreground_color);
+                // On-colors are generated to meet the contrast of AA
+                assert!(on_color.passes, "{:?}", on_color);
+            }
+        }
+
+        // Without on-colors, only the text and the theme colors are checked
+        let plain = contrast_checks(&UI_CONFIGURATION, requirement(Level::Aa, false, None));
+        assert_eq!(plain.len(), 2 * (1 + colors));
+    }
+}
@@ -0,0 +1,536 @@
+//! OKLCH colors and their conversion to the RGB color spaces of displays.
+//!
+//! Theme colors are configured in OKLCH, where chroma can go far beyond what a display shows.
+//! Colors are brought into a gamut with the CSS Color 4 gamut mapping algorithm, which lowers
+//! the chroma at the same lightness and hue until clipping the color changes it by less than a
+//! just noticeable difference. That final clip can still turn the hue by a few degrees, e.g.
+//! the default `oklch(0.9 0.7 0)` is shown in sRGB at a hue of about 355, but far less
+//! than clipping the configured color would.
+
+use serde_json::Number;
+
+/// The difference in OKLab below which two colors look the same.
+const JUST_NOTICEABLE_DIFFERENCE: f64 = 0.02;
+
+/// The precision of the chroma search when mapping a color into a gamut.
+const CHROMA_EPSILON: f64 = 0.0001;
+
+/// The precision of the lightness search when finding an on-color.
+const LIGHTNESS_EPSILON: f64 = 0.0001;
+
+/// The RGB color space of a display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gamut {
+    /// The color space of most displays, and of hex colors.
+    Srgb,
+    /// The wider color space of recent phones and laptops.
+    DisplayP3,
+}
+
+impl Gamut {
+    /// Returns the name of the color space.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Srgb => "sRGB",
+            Self::DisplayP3 => "Display P3",
+        }
+    }
+
+    /// The matrix converting CIE XYZ to the linear RGB of the color space.
+    fn xyz_to_rgb(self) -> [[f64; 3]; 3] {
+        match self {
+            Self::Srgb => [
+                [3.2409699419045226, -1.537383177570094, -0.4986107602930034],
+                [-0.9692436362808796, 1.8759675015077202, 0.04155505740717559],
+                [
+                    0.05563007969699366,
+                    -0.20397695888897652,
+                    1.0569715142428786,
+                ],
+            ],
+            Self::DisplayP3 => [
+                [
+                    2.4934969119414254,
+                    -0.9313836179191239,
+                    -0.40271078445071684,
+                ],
+                [
+                    -0.8294889695615747,
+                    1.7626640603183463,
+                    0.023624685841943577,
+                ],
+                [
+                    0.03584583024378447,
+                    -0.07617238926804182,
+                    0.9568845240076872,
+                ],
+            ],
+        }
+    }
+
+    /// The matrix converting the linear RGB of the color space to CIE XYZ.
+    fn rgb_to_xyz(self) -> [[f64; 3]; 3] {
+        match self {
+            Self::Srgb => [
+                [0.41239079926595934, 0.357584339383878, 0.1804807884018343],
+                [0.21263900587151027, 0.715168678767756, 0.07219231536073371],
+                [0.01933081871559182, 0.11919477979462598, 0.9505321522496606],
+            ],
+            Self::DisplayP3 => [
+                [0.4865709486482162, 0.26566769316909306, 0.1982172852343625],
+                [0.2289745640697488, 0.6917385218365064, 0.079286914093745],
+                [0.0, 0.04511338185890264, 1.043944368900976],
+            ],
+        }
+    }
+}
+
+/// A color in the OKLCH color space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    /// The perceived lightness, from `0` (black) to `1` (white).
+    pub lightness: f64,
+    /// The colorfulness, from `0` (gray) upwards.
+    pub chroma: f64,
+    /// The hue angle, in degrees.
+    pub hue: f64,
+}
+
+impl Oklch {
+    pub fn new(lightness: f64, chroma: f64, hue: f64) -> Self {
+        Self {
+            lightness,
+            chroma,
+            hue,
+        }
+    }
+
+    /// Creates a color from configuration values.
+    pub fn from_numbers(lightness: &Number, chroma: &Number, hue: &Number) -> Self {
+        let value = |number: &Number| number.as_f64().unwrap_or_default();
+        Self::new(value(lightness), value(chroma), value(hue))
+    }
+
+    /// Returns whether a display with the gamut can show the color.
+    pub fn in_gamut(self, gamut: Gamut) -> bool {
+        // Allow for rounding errors, e.g. in white
+        const TOLERANCE: f64 = 0.000_1;
+        self.to_linear_rgb(gamut)
+            .iter()
+            .all(|channel| (-TOLERANCE..=1.0 + TOLERANCE).contains(channel))
+    }
+
+    /// Maps the color into a gamut with the CSS Color 4 gamut mapping algorithm: the chroma is
+    /// lowered until clipping the color to the gamut no longer makes a visible difference.
+    ///
+    /// # Arguments
+    /// * `gamut` - The gamut to map the color into.
+    ///
+    /// # Returns
+    /// `Oklch` - The closest color the gamut can show; the color itself if it fits.
+    pub fn map_to_gamut(self, gamut: Gamut) -> Oklch {
+        if self.lightness >= 1.0 {
+            return Self::new(1.0, 0.0, self.hue);
+        }
+        if self.lightness <= 0.0 {
+            return Self::new(0.0, 0.0, self.hue);
+        }
+        if self.in_gamut(gamut) {
+            return self;
+        }
+
+        let clipped = self.clip(gamut);
+        if clipped.difference(self) < JUST_NOTICEABLE_DIFFERENCE {
+            return clipped;
+        }
+
+        // Binary search for the highest chroma whose clipped color looks the same
+        let mut current = self;
+        let mut min = 0.0;
+        let mut max = self.chroma;
+        let mut min_in_gamut = true;
+        let mut clipped = clipped;
+        while max - min > CHROMA_EPSILON {
+            current.chroma = (min + max) / 2.0;
+            if min_in_gamut && current.in_gamut(gamut) {
+                min = current.chroma;
+                continue;
+            }
+
+            clipped = current.clip(gamut);
+            let difference = clipped.difference(current);
+            if difference < JUST_NOTICEABLE_DIFFERENCE {
+                if JUST_NOTICEABLE_DIFFERENCE - difference < CHROMA_EPSILON {
+                    break;
+                }
+                min_in_gamut = false;
+                min = current.chroma;
+            } else {
+                max = current.chroma;
+            }
+        }
+        clipped
+    }
+
+    /// Returns the RGB channels of the color in a gamut, from `0` to `1`, after mapping it into
+    /// the gamut.
+    pub fn to_rgb(self, gamut: Gamut) -> [f64; 3] {
+        self.map_to_gamut(gamut)
+            .to_linear_rgb(gamut)
+            .map(|channel| encode(channel.clamp(0.0, 1.0)))
+    }
+
+    /// Formats the color as an sRGB hex color, e.g. `#ff8800`, after mapping it into sRGB.
+    pub fn to_hex(self) -> String {
+        let [red, green, blue] = self.to_bytes();
+        format!("#{:02x}{:02x}{:02x}", red, green, blue)
+    }
+
+    /// Returns the 8-bit sRGB channels of the color, as written in hex colors.
+    fn to_bytes(self) -> [u8; 3] {
+        self.to_rgb(Gamut::Srgb)
+            .map(|channel| (channel * 255.0).round() as u8)
+    }
+
+    /// Returns the sRGB channels of the color as shown, from `0` to `1`, with the precision of
+    /// hex colors, so contrasts are measured on the colors written to the stylesheet.
+    fn to_shown_rgb(self) -> [f64; 3] {
+        self.to_bytes().map(|channel| f64::from(channel) / 255.0)
+    }
+
+    /// Returns the relative luminance of the color as shown on a display with the gamut: the
+    /// hex color where sRGB can show it, as the stylesheet falls back to it, and otherwise the
+    /// color mapped into the gamut.
+    fn shown_luminance(self, gamut: Gamut) -> f64 {
+        if gamut != Gamut::Srgb && !self.in_gamut(Gamut::Srgb) {
+            return self.map_to_gamut(gamut).to_xyz()[1];
+        }
+
+        // Relative luminance, from the WCAG 2 definition
+        let [red, green, blue] = self.to_shown_rgb().map(|channel| {
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        0.2126 * red + 0.7152 * green + 0.0722 * blue
+    }
+
+    /// Formats the color as a CSS `oklch()` color, e.g. `oklch(0.7 0.15 210)`.
+    pub fn to_css(self) -> String {
+        format!(
+            "oklch({} {} {})",
+            round(self.lightness),
+            round(self.chroma),
+            round(self.hue)
+        )
+    }
+
+    /// Returns the Euclidean distance to another color in OKLab, where `0.02` is just noticeable.
+    pub fn difference(self, other: Oklch) -> f64 {
+        let [l1, a1, b1] = self.to_oklab();
+        let [l2, a2, b2] = other.to_oklab();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    /// Clips the RGB channels of the color to a gamut.
+    fn clip(self, gamut: Gamut) -> Oklch {
+        let rgb = self
+            .to_linear_rgb(gamut)
+            .map(|channel| channel.clamp(0.0, 1.0));
+        let xyz = multiply(gamut.rgb_to_xyz(), rgb);
+        Self::from_oklab(multiply(
+            LMS_TO_OKLAB,
+            multiply(XYZ_TO_LMS, xyz).map(f64::cbrt),
+        ))
+    }
+
+    fn to_oklab(self) -> [f64; 3] {
+        let hue = self.hue.to_radians();
+        [
+            self.lightness,
+            self.chroma * hue.cos(),
+            self.chroma * hue.sin(),
+        ]
+    }
+
+    fn from_oklab([lightness, a, b]: [f64; 3]) -> Self {
+        let chroma = a.hypot(b);
+        // Grays have no hue; keep 0 rather than a rounding artefact
+        let hue = if chroma < 1e-9 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        };
+        Self::new(lightness, chroma, hue)
+    }
+
+    /// Converts the color to the linear RGB channels of a gamut, which are outside `0..=1` for
+    /// colors it cannot show.
+    fn to_linear_rgb(self, gamut: Gamut) -> [f64; 3] {
+        multiply(gamut.xyz_to_rgb(), self.to_xyz())
+    }
+
+    /// Converts the color to CIE XYZ, where Y is the relative luminance.
+    fn to_xyz(self) -> [f64; 3] {
+        let lms = multiply(OKLAB_TO_LMS, self.to_oklab()).map(|value| value.powi(3));
+        multiply(LMS_TO_XYZ, lms)
+    }
+}
+
+/// Returns the WCAG 2 contrast ratio between two colors as shown on displays with a gamut,
+/// from `1` (none) to `21` (black on white).
+pub fn contrast_ratio(foreground: Oklch, background: Oklch, gamut: Gamut) -> f64 {
+    let (first, second) = (
+        foreground.shown_luminance(gamut),
+        background.shown_luminance(gamut),
+    );
+    (first.max(second) + 0.05) / (first.min(second) + 0.05)
+}
+
+/// Returns the CIE L* perceived lightness of an OKLCH lightness, from `0` (black) to `100`
+/// (white). Unlike the OKLCH lightness, equal steps of L* look equal near black too.
+pub fn perceived_lightness(lightness: f64) -> f64 {
+    // The relative luminance of a gray of the lightness
+    let luminance = lightness.max(0.0).powi(3);
+    if luminance > 216.0 / 24_389.0 {
+        116.0 * luminance.cbrt() - 16.0
+    } else {
+        luminance * 24_389.0 / 27.0
+    }
+}
+
+/// Returns the OKLCH lightness of a CIE L* perceived lightness; the inverse of
+/// [`perceived_lightness`].
+pub fn oklch_lightness(perceived: f64) -> f64 {
+    let luminance = if perceived > 8.0 {
+        ((perceived + 16.0) / 116.0).powi(3)
+    } else {
+        perceived.max(0.0) * 27.0 / 24_389.0
+    };
+    luminance.cbrt()
+}
+
+/// Finds a foreground for text on a color: the foreground keeps the hue of the color and gets
+/// the lightness closest to it that meets the contrast, toward black or white, whichever
+/// contrasts more with the color.
+///
+/// # Arguments
+/// * `color` - The color the foreground is shown on.
+/// * `contrast` - The WCAG 2 contrast ratio to meet.
+/// * `chroma` - The chroma of the foreground.
+/// * `gamut` - The gamut of the displays both colors are shown on.
+///
+/// # Returns
+/// `Oklch` - The foreground; the one with the most contrast if none meets it.
+pub fn on_color(color: Oklch, contrast: f64, chroma: f64, gamut: Gamut) -> Oklch {
+    let contrast_ratio = |foreground, background| contrast_ratio(foreground, background, gamut);
+    let foreground = |lightness: f64| Oklch::new(lightness, chroma, color.hue);
+    let (black, white) = (foreground(0.0), foreground(1.0));
+    let extreme = if contrast_ratio(black, color) >= contrast_ratio(white, color) {
+        0.0
+    } else {
+        1.0
+    };
+    if contrast_ratio(foreground(extreme), color) < contrast {
+        return foreground(extreme);
+    }
+
+    // Binary search between the color and the extreme, keeping a lightness that meets it
+    let (mut near, mut far) = (color.lightness.clamp(0.0, 1.0), extreme);
+    while (far - near).abs() > LIGHTNESS_EPSILON {
+        let middle = (near + far) / 2.0;
+        if contrast_ratio(foreground(middle), color) >= contrast {
+            far = middle;
+        } else {
+            near = middle;
+        }
+    }
+    foreground(far)
+}
+
+/// Returns the APCA lightness contrast (Lc) of text on a background as shown on sRGB displays,
+/// from about `106` (black on white) to `-108` (white on black). Negative values are light
+/// text on dark backgrounds.
+pub fn apca_contrast(text: Oklch, background: Oklch) -> f64 {
+    // Screen luminance, soft-clamped near black, from APCA 0.0.98G-4g
+    let luminance = |color: Oklch| {
+        let [red, green, blue] = color.to_shown_rgb().map(|channel| channel.powf(2.4));
+        let luminance = 0.2126729 * red + 0.7151522 * green + 0.0721750 * blue;
+        if luminance < 0.022 {
+            luminance + (0.022 - luminance).powf(1.414)
+        } else {
+            luminance
+        }
+    };
+    let (text, background) = (luminance(text), luminance(background));
+    if (background - text).abs() < 0.0005 {
+        return 0.0;
+    }
+
+    let contrast = if background > text {
+        // Dark text on a light background
+        let contrast = (background.powf(0.56) - text.powf(0.57)) * 1.14;
+        if contrast < 0.1 {
+            0.0
+        } else {
+            contrast - 0.027
+        }
+    } else {
+        // Light text on a dark background
+        let contrast = (background.powf(0.65) - text.powf(0.62)) * 1.14;
+        if contrast > -0.1 {
+            0.0
+        } else {
+            contrast + 0.027
+        }
+    };
+    contrast * 100.0
+}
+
+const OKLAB_TO_LMS: [[f64; 3]; 3] = [
+    [1.0, 0.3963377773761749, 0.2158037573099136],
+    [1.0, -0.1055613458156586, -0.0638541728258133],
+    [1.0, -0.0894841775298119, -1.2914855480194092],
+];
+
+const LMS_TO_OKLAB: [[f64; 3]; 3] = [
+    [0.210454268309314, 0.7936177747023054, -0.0040720430116193],
+    [1.9779985324311684, -2.42859224204858, 0.450593709617411],
+    [0.0259040424655478, 0.7827717124575296, -0.8086757660078722],
+];
+
+const LMS_TO_XYZ: [[f64; 3]; 3] = [
+    [1.2268798758459243, -0.5578149944602171, 0.2813910456659647],
+    [-0.0405757452148008, 1.112286803280317, -0.0717110580655164],
+    [-0.0763729366746601, -0.4214933239627915, 1.5869240198367816],
+];
+
+const XYZ_TO_LMS: [[f64; 3]; 3] = [
+    [0.819022437996703, 0.3619062600528904, -0.1288737815209879],
+    [0.0329836539323885, 0.9292868615863434, 0.0361446663506424],
+    [0.0481771893596242, 0.2642395317527308, 0.6335478284694309],
+];
+
+fn multiply(matrix: [[f64; 3]; 3], vector: [f64; 3]) -> [f64; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+/// Applies the transfer function shared by sRGB and Display P3.
+fn encode(channel: f64) -> f64 {
+    if channel > 0.0031308 {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    } else {
+        12.92 * channel
+    }
+}
+
+/// Rounds a color component to 4 decimals, which is below any visible difference.
+fn round(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the sRGB gray with the channel, e.g. `0x88` for `#888888`.
+    fn gray(channel: u8) -> Oklch {
+        let channel = f64::from(channel) / 255.0;
+        let linear = if channel <= 0.04045 {
+            channel / 12.92
+        } else {
+            ((channel + 0.055) / 1.055).powf(2.4)
+        };
+        Oklch::new(linear.cbrt(), 0.0, 0.0)
+    }
+
+    #[test]
+    fn white_and_black_hex() {
+        assert_eq!(Oklch::new(1.0, 0.0, 0.0).to_hex(), "#ffffff");
+        assert_eq!(Oklch::new(0.0, 0.0, 0.0).to_hex(), "#000000");
+    }
+
+    #[test]
+    fn converts_oklch_to_srgb() {
+        // The sRGB primaries, as given by the CSS Color 4 specification
+        assert_eq!(Oklch::new(0.627955, 0.257683, 29.2339).to_hex(), "#ff0000");
+        assert_eq!(Oklch::new(0.86644, 0.294827, 142.4953).to_hex(), "#00ff00");
+        assert_eq!(Oklch::new(0.452014, 0.313214, 264.052).to_hex(), "#0000ff");
+    }
+
+    #[test]
+    fn mapped_colors_are_in_gamut() {
+        for gamut in [Gamut::Srgb, Gamut::DisplayP3] {
+            for hue in (0..360).step_by(15) {
+                for lightness in [0.1, 0.5, 0.9] {
+                    let color = Oklch::new(lightness, 0.7, f64::from(hue));
+                    let mapped = color.map_to_gamut(gamut);
+                    assert!(mapped.in_gamut(gamut), "{:?} in {}", color, gamut.name());
+                    assert!(mapped.chroma < color.chroma);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn colors_in_gamut_are_kept() {
+        let color = Oklch::new(0.7, 0.1, 210.0);
+        assert_eq!(color.map_to_gamut(Gamut::Srgb), color);
+        assert_eq!(color.map_to_gamut(Gamut::DisplayP3), color);
+    }
+
+    #[test]
+    fn mapping_barely_turns_the_hue() {
+        let mapped = Oklch::new(0.9, 0.7, 0.0).map_to_gamut(Gamut::Srgb);
+        assert!((mapped.hue - 355.3).abs() < 0.1, "{:?}", mapped);
+    }
+
+    #[test]
+    fn wcag_contrast_of_reference_pairs() {
+        let (black, white) = (gray(0x00), gray(0xff));
+        assert_eq!(gray(0x77).to_hex(), "#777777");
+        assert!((contrast_ratio(black, white, Gamut::Srgb) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(white, black, Gamut::Srgb) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(white, white, Gamut::Srgb) - 1.0).abs() < 1e-9);
+        // Just short of AA, however it is rounded for display
+        let ratio = contrast_ratio(gray(0x77), white, Gamut::Srgb);
+        assert!((ratio - 4.478).abs() < 0.001, "{}", ratio);
+    }
+
+    #[test]
+    fn perceived_lightness_round_trips() {
+        assert_eq!(perceived_lightness(0.0), 0.0);
+        assert!((perceived_lightness(1.0) - 100.0).abs() < 1e-9);
+        // Middle gray, #777777, is about halfway in L*
+        let middle = perceived_lightness(gray(0x77).lightness);
+        assert!((middle - 50.0).abs() < 0.5, "{}", middle);
+        for perceived in [0.0, 2.0, 8.0, 25.0, 50.0, 96.0, 100.0] {
+            let lightness = oklch_lightness(perceived);
+            assert!((perceived_lightness(lightness) - perceived).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn apca_contrast_of_reference_pairs() {
+        // From the APCA 0.0.98G-4g test values
+        for (text, background, expected) in [
+            (0x00, 0xff, 106.04),
+            (0xff, 0x00, -107.88),
+            (0x88, 0xff, 63.06),
+            (0xff, 0x88, -68.54),
+            (0x00, 0xaa, 58.15),
+            (0xaa, 0x00, -56.24),
+        ] {
+            let contrast = apca_contrast(gray(text), gray(background));
+            assert!(
+                (contrast - expected).abs() < 0.01,
+                "{:#04x} on {:#04x}: {}",
+                text,
+                background,
+                contrast
+            );
+        }
+    }
+}
@@ -1,10 +1,15 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use crate::{
-    config::types::Config,
+    config::{
+        types::{Config, LoadOptions, LoadedConfig},
+        validate::{ValidationError, check_output_dir},
+        workspace::workspace_members,
+    },
     helpers::{
-        cli::{spinner_done, spinner_progress, spinner_start},
+        cli::{error_text, spinner_done, spinner_failed, spinner_start},
         fs::{create_path_from_file_name, load_seyuna_user_config, save_file},
     },
 };
@@ -15,42 +20,67 @@ use lightningcss::{
     stylesheet::{MinifyOptions, ParserOptions, StyleSheet},
     targets::{Browsers, Targets},
 };
-use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{EventKind, RecommendedWatcher, Watcher};
+use parcel_sourcemap::SourceMap;
+use serde_json::Number;
+use sha2::{Digest, Sha256};
+use tokio::fs;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 
+use super::{
+    color::{Gamut, Oklch, oklch_lightness, perceived_lightness},
+    types::{Build, Color, Mode, Palette, PrimaryVariable, ScaleCurve, Theme},
+    workspace::{compile_workspace, layer_files, watch_files},
+};
+
+/// The file name of the compiled stylesheet, before hashing.
+const STYLESHEET_FILE_NAME: &str = "seyuna-global.css";
+
+/// The file listing the hashed file name of the stylesheet.
+const MANIFEST_FILE_NAME: &str = "seyuna-manifest.json";
+
 /// Entrypoint for the `compile` CLI command.
 /// Loads the user's configuration and triggers CSS compilation.
 ///
 /// # Arguments
-/// * `_matches` - CLI argument matches (unused).
+/// * `matches` - CLI argument matches.
 ///
 /// # Returns
-/// * `Result<Config>` - The loaded configuration or an error.
-pub async fn compile(matches: &ArgMatches) -> Result<Config> {
+/// * `Result<LoadedConfig>` - The loaded configuration or an error.
+pub async fn compile(matches: &ArgMatches) -> Result<LoadedConfig> {
     // Load the user's configuration file asynchronously
-    let config = load_seyuna_user_config().await?;
+    let loaded = load_seyuna_user_config(&LoadOptions::from_matches(matches)).await?;
+    print_diagnostics(&loaded);
+
+    // A workspace root compiles its members instead of itself
+    if let Some(members) = workspace_members(&loaded)? {
+        compile_workspace(&loaded, members, matches.get_flag("watch")).await?;
+        return Ok(loaded);
+    }
 
     // Compile CSS based on the loaded configuration
-    compile_css(&config).await?;
+    compile_css(&loaded).await?;
 
-    // If the --watch flag is set, start watching for changes to seyuna.json
+    // If the --watch flag is set, start watching for changes to the configuration file
     if matches.get_flag("watch") {
         println!("Seyuna watcher active...");
 
-        // Create a channel for receiving file change events
-        let (tx, mut rx) = mpsc::channel(1);
+        // Create a channel for receiving file change events. It is unbounded, so the watcher
+        // never blocks on sending while it is asked to watch another file.
+        let (tx, mut rx) = mpsc::unbounded_channel();
 
         // Initialize the file watcher with a callback that sends events to the channel
         let mut watcher = RecommendedWatcher::new(
             move |res| {
-                let _ = tx.blocking_send(res);
+                let _ = tx.send(res);
             },
             notify::Config::default(),
         )?;
 
-        // Watch the seyuna.json file for changes (non-recursive)
-        watcher.watch(Path::new("seyuna.json"), RecursiveMode::NonRecursive)?;
+        // Watch the configuration file and every file it extends for changes (non-recursive)
+        let mut watched = HashSet::new();
+        watch_files(&mut watcher, &mut watched, &layer_files(&loaded))?;
 
         // Track the last event time for debouncing
         let mut last_event = Instant::now();
@@ -66,10 +96,31 @@ pub async fn compile(matches: &ArgMatches) -> Result<Config> {
                             // Wait a bit to ensure the file write is finished
                             sleep(Duration::from_millis(500)).await;
 
-                            // Reload the user's configuration file asynchronously
-                            let config = load_seyuna_user_config().await?;
+                            // Reload the user's configuration file asynchronously. Errors,
+                            // e.g. from a typo saved mid-edit, are printed and the watcher
+                            // keeps running until the file is fixed.
+                            let reloaded = load_seyuna_user_config(&LoadOptions {
+                                config_path: Some(loaded.path.clone()),
+                                ..loaded.options.clone()
+                            })
+                            .await;
+                            match reloaded {
+                                Ok(reloaded) => {
+                                    print_diagnostics(&reloaded);
+
+                                    // Watch files newly added through `extends`
+                                    watch_files(
+                                        &mut watcher,
+                                        &mut watched,
+                                        &layer_files(&reloaded),
+                                    )?;
 
-                            compile_css(&config).await?;
+                                    if let Err(e) = compile_css(&reloaded).await {
+                                        eprintln!("{}", error_text(&format!("{:#}", e)));
+                                    }
+                                }
+                                Err(e) => eprintln!("{}", error_text(&format!("{:#}", e))),
+                            }
                             last_event = Instant::now();
                         }
                     }
@@ -81,58 +132,187 @@ pub async fn compile(matches: &ArgMatches) -> Result<Config> {
     }
 
     // Return the loaded configuration
-    Ok(config)
+    Ok(loaded)
+}
+
+/// Prints the warnings found while loading the configuration.
+///
+/// # Arguments
+/// * `loaded` - Reference to the loaded Seyuna configuration.
+pub fn print_diagnostics(loaded: &LoadedConfig) {
+    for diagnostic in loaded.diagnostics() {
+        eprintln!("{}\n", diagnostic.render());
+    }
 }
 
 /// Compiles CSS based on the provided configuration.
 ///
 /// # Arguments
-/// * `config` - Reference to the loaded Seyuna configuration.
+/// * `loaded` - Reference to the loaded Seyuna configuration.
 ///
 /// # Returns
 /// * `Result<()>` - No value.
-pub async fn compile_css(config: &Config) -> Result<()> {
+pub async fn compile_css(loaded: &LoadedConfig) -> Result<()> {
     // Start spinner
-    let spinner = spinner_start("Compiling CSS");
+    let spinner = spinner_start(&match &loaded.options.profile {
+        Some(profile) => format!("Compiling CSS ({} profile)", profile),
+        None => "Compiling CSS".to_string(),
+    });
 
-    // Extract the UI configuration from the main config
-    let ui_config = config
+    // Compile and save the stylesheet
+    if let Err(e) = write_stylesheet(loaded).await {
+        spinner_failed(&spinner, "Failed to compile Seyuna");
+        return Err(e);
+    }
+
+    // Display completed progress
+    spinner_done(&spinner, "Successfully compiled Seyuna!");
+
+    Ok(())
+}
+
+/// Compiles the stylesheet of a configuration and saves it in the output directory, along
+/// with its source map when enabled and the manifest listing the written files.
+///
+/// # Arguments
+/// * `loaded` - Reference to the loaded Seyuna configuration.
+///
+/// # Returns
+/// * `Result<PathBuf>` - The path of the saved stylesheet.
+pub async fn write_stylesheet(loaded: &LoadedConfig) -> Result<PathBuf> {
+    // Build settings
+    let build = loaded
+        .config
         .ui
-        .clone()
-        .context("UI configuration missing in Seyuna config")?;
+        .as_ref()
+        .context("UI configuration missing in Seyuna config")?
+        .build;
+
+    // Make sure the compiled files can be written
+    if let Some(diagnostic) = check_output_dir(loaded) {
+        return Err(ValidationError {
+            diagnostics: vec![diagnostic],
+        }
+        .into());
+    }
+
+    let (mut code, source_map) = render_stylesheet(&loaded.config, build)?;
+
+    // Name the file after its content when hashing, so it can be cached forever
+    let output_dir = loaded.output_dir()?;
+    let file_name = if build.hash {
+        let hash = Sha256::digest(code.as_bytes());
+        let hash: String = hash[..4]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("seyuna-global.{}.css", hash)
+    } else {
+        STYLESHEET_FILE_NAME.to_string()
+    };
+
+    // Define file path, relative to the configuration file
+    let file_path = create_path_from_file_name(&file_name, &output_dir)?;
+
+    // Files written by the previous compile, before its manifest is replaced
+    let previous = read_manifest(&output_dir).await;
+
+    // Files written by this compile, listed in the manifest
+    let mut manifest = serde_json::Map::new();
+    manifest.insert(STYLESHEET_FILE_NAME.to_string(), file_name.clone().into());
 
+    // Save the source map next to the stylesheet
+    if let Some(source_map) = source_map {
+        let map_file_name = format!("{}.map", file_name);
+        manifest.insert(
+            format!("{}.map", STYLESHEET_FILE_NAME),
+            map_file_name.clone().into(),
+        );
+        save_file(
+            create_path_from_file_name(&map_file_name, &output_dir)?,
+            source_map.as_bytes(),
+        )
+        .await?;
+        code += &format!("\n/*# sourceMappingURL={} */\n", map_file_name);
+    }
+
+    // Save the stylesheet
+    save_file(&file_path, code.as_bytes()).await?;
+
+    // List the written file names in the manifest
+    save_file(
+        create_path_from_file_name(MANIFEST_FILE_NAME, &output_dir)?,
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+    )
+    .await?;
+
+    // Remove what the previous compile wrote, possibly with other build settings
+    let written: Vec<String> = manifest
+        .values()
+        .filter_map(|name| name.as_str().map(str::to_string))
+        .collect();
+    remove_outdated_files(&output_dir, &previous, &written).await?;
+
+    Ok(file_path)
+}
+
+/// Generates, minifies and prints the stylesheet of a configuration.
+///
+/// # Arguments
+/// * `config` - Reference to the merged Seyuna configuration.
+/// * `build` - How the stylesheet is written.
+///
+/// # Returns
+/// * `Result<(String, Option<String>)>` - The CSS code, and the JSON source map when enabled.
+fn render_stylesheet(config: &Config, build: Build) -> Result<(String, Option<String>)> {
     // CSS reset string
     let reset_string = include_str!("reset.css").to_string();
 
     // CSS variables string
-    let variables_string = css_variables(&config).unwrap();
+    let variables_string = css_variables(config)?;
 
     // Merge the css strings into a static string before passing it to the Stylesheet
     let merged_css_string: &'static str =
         Box::leak((reset_string + &variables_string).into_boxed_str());
 
     // Parse the merged css as Stylesheet
-    let mut stylesheet = StyleSheet::parse(&merged_css_string, ParserOptions::default())
-        .with_context(|| {
-            format!(
-                "Failed to parse generated CSS content as a StyleSheet. \
-                Content length: {}. First 200 chars: {:?}",
-                merged_css_string.len(),
-                &merged_css_string[..merged_css_string.len().min(200)]
-            )
-        })?;
+    let mut stylesheet = StyleSheet::parse(
+        merged_css_string,
+        ParserOptions {
+            filename: STYLESHEET_FILE_NAME.to_string(),
+            ..ParserOptions::default()
+        },
+    )
+    .with_context(|| {
+        format!(
+            "Failed to parse generated CSS content as a StyleSheet. \
+            Content length: {}. First 200 chars: {:?}",
+            merged_css_string.len(),
+            &merged_css_string[..merged_css_string.len().min(200)]
+        )
+    })?;
 
     // Minify the stylesheet
-    stylesheet.minify(MinifyOptions::default()).context(
-        "Failed to minify StyleSheet. The CSS may contain invalid or unsupported syntax.",
-    )?;
-
-    // Define file path
-    let file_path =
-        create_path_from_file_name("seyuna-global.css", &ui_config.output_dir.unwrap())?;
+    if build.minify {
+        stylesheet.minify(MinifyOptions::default()).context(
+            "Failed to minify StyleSheet. The CSS may contain invalid or unsupported syntax.",
+        )?;
+    }
 
     // Convert stylesheet in to formatted css code string
+    let mut source_map = if build.source_map {
+        let mut source_map = SourceMap::new("/");
+        source_map.add_source(STYLESHEET_FILE_NAME);
+        source_map
+            .set_source_content(0, merged_css_string)
+            .map_err(|e| anyhow::anyhow!("Failed to create the source map: {}", e))?;
+        Some(source_map)
+    } else {
+        None
+    };
     let css_code = stylesheet.to_css(PrinterOptions {
+        minify: build.minify,
+        source_map: source_map.as_mut(),
         targets: Targets {
             browsers: Some(Browsers {
                 chrome: Some(80),
@@ -143,18 +323,71 @@ pub async fn compile_css(config: &Config) -> Result<()> {
         ..PrinterOptions::default()
     })?;
 
-    // Display progress
-    spinner_progress(&spinner, &format!("Saving {:?}...", &file_path));
+    // Serialize the source map
+    let source_map = source_map
+        .map(|mut source_map| source_map.to_json(None))
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Failed to generate the source map: {}", e))?;
 
-    // Save seyuna-global.css file
-    save_file(file_path, css_code.code.as_bytes()).await?;
+    Ok((css_code.code, source_map))
+}
 
-    // Display completed progress
-    spinner_done(&spinner, "Successfully compiled Seyuna!");
+/// Reads the file names listed in the manifest of the previous compilation. A missing or
+/// unreadable manifest lists nothing, and names that are not plain file names are skipped so
+/// that nothing outside the output directory is ever removed.
+///
+/// # Arguments
+/// * `output_dir` - The directory the stylesheets are written to.
+///
+/// # Returns
+/// * `Vec<String>` - The file names written by the previous compilation.
+async fn read_manifest(output_dir: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(output_dir.join(MANIFEST_FILE_NAME)).await else {
+        return Vec::new();
+    };
+    let Ok(serde_json::Value::Object(manifest)) = serde_json::from_str(&content) else {
+        return Vec::new();
+    };
+    manifest
+        .values()
+        .filter_map(serde_json::Value::as_str)
+        .filter(|name| {
+            let path = Path::new(name);
+            path.file_name() == Some(path.as_os_str()) && *name != MANIFEST_FILE_NAME
+        })
+        .map(str::to_string)
+        .collect()
+}
 
+/// Removes the files of the previous compilation that this one did not write, e.g. a
+/// stylesheet named after an outdated hash, the unhashed stylesheet once hashing is enabled,
+/// or a source map once it is disabled. Only files listed in the previous manifest are
+/// touched, so files of other tools and users are left alone.
+///
+/// # Arguments
+/// * `output_dir` - The directory the stylesheets are written to.
+/// * `previous` - The file names listed in the previous manifest.
+/// * `written` - The file names written by this compilation.
+///
+/// # Returns
+/// * `Result<()>` - No value.
+async fn remove_outdated_files(
+    output_dir: &Path,
+    previous: &[String],
+    written: &[String],
+) -> Result<()> {
+    for name in previous.iter().filter(|name| !written.contains(name)) {
+        match fs::remove_file(output_dir.join(name)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
     Ok(())
 }
 
+/// CSS declarations of custom properties, as variable names without `--` and their values.
+type Declarations = Vec<(String, String)>;
+
 pub fn css_variables(config: &Config) -> Result<String> {
     // Extract the UI configuration from the main config
     let ui_config = config
@@ -163,103 +396,1000 @@ pub fn css_variables(config: &Config) -> Result<String> {
         .context("UI configuration missing in Seyuna config")?;
 
     // CSS result string
-    let mut result = String::new();
+    let light = light_mode_variables(config, Gamut::Srgb)?;
+    let dark = dark_mode_variables(config, Gamut::Srgb)?;
+    let mut result = mode_rules(
+        ui_config.mode,
+        declarations_css(&root_variables(&ui_config.theme)),
+        &declarations_css(&light),
+        &declarations_css(&dark),
+    );
 
-    // Root variables
-    result += ":root {";
-    // Loop through the palette colors
-    for color in &ui_config.theme.colors {
-        // Add the color variable
-        result += &format!("--{}:{};", color.0, color.1);
+    // Wide-gamut displays get the colors sRGB cannot show, in browsers supporting oklch().
+    // Every mode redeclares each upgraded variable, with its hex color where sRGB can show
+    // it, so the defaults of one mode never leak into an explicit data-mode of the other.
+    let light_p3 = light_mode_variables(config, Gamut::DisplayP3)?;
+    let dark_p3 = dark_mode_variables(config, Gamut::DisplayP3)?;
+    let upgraded: Vec<&str> = light
+        .iter()
+        .zip(&light_p3)
+        .chain(dark.iter().zip(&dark_p3))
+        .filter(|(srgb, p3)| srgb.1 != p3.1)
+        .map(|(_, (name, _))| name.as_str())
+        .collect();
+    if !upgraded.is_empty() {
+        let upgrades = |declarations: &Declarations| {
+            let declarations: Declarations = declarations
+                .iter()
+                .filter(|(name, _)| upgraded.contains(&name.as_str()))
+                .cloned()
+                .collect();
+            declarations_css(&declarations)
+        };
+        result += "@media (color-gamut: p3) {";
+        result += "@supports (color: oklch(0 0 0)) {";
+        result += &mode_rules(
+            ui_config.mode,
+            String::new(),
+            &upgrades(&light_p3),
+            &upgrades(&dark_p3),
+        );
+        result += "}";
+        result += "}";
     }
+
+    // Default html css settings
+    result += "html {";
+    result += "color: var(--text);";
+    result += "background-color: var(--background);";
+    result += "font-size: max(1rem, 0.833vw);";
     result += "}";
 
+    Ok(result)
+}
+
+/// Lays out the rules of the root and of every color mode.
+///
+/// # Arguments
+/// * `mode` - The configured mode, whose variables the root gets by default.
+/// * `root` - The declarations only the root gets.
+/// * `light` - The declarations of the light mode.
+/// * `dark` - The declarations of the dark mode.
+///
+/// # Returns
+/// `String` - The CSS rules, leaving out rules without any declaration.
+fn mode_rules(mode: Mode, root: String, light: &str, dark: &str) -> String {
+    // Wraps declarations in a rule, leaving out rules without any
+    let rule = |selector: &str, declarations: String| {
+        if declarations.is_empty() {
+            String::new()
+        } else {
+            format!("{} {{{}}}", selector, declarations)
+        }
+    };
+
+    // CSS result string
+    let mut result = String::new();
+
+    // Default mode variables, used when no data-mode attribute is set
+    match mode {
+        Mode::Light => result += &rule(":root", root + light),
+        Mode::Dark => result += &rule(":root", root + dark),
+        Mode::System => {
+            result += &rule(":root", root + light);
+            result += &rule(
+                "@media (prefers-color-scheme: dark)",
+                rule(":root", dark.to_string()),
+            );
+        }
+    }
+
+    // Explicit data-mode attributes come after the defaults, so they override them
+
     // Light mode variables
-    result += "[data-mode=\"light\"] {";
-    result += &light_mode_variables(&config).unwrap();
-    result += "}";
+    result += &rule("[data-mode=\"light\"]", light.to_string());
 
     // Dark mode variables
-    result += "[data-mode=\"dark\"] {";
-    result += &dark_mode_variables(&config).unwrap();
-    result += "}";
+    result += &rule("[data-mode=\"dark\"]", dark.to_string());
 
     // System mode | light variables
-    result += "@media (prefers-color-scheme: light) {";
-    result += "[data-mode=\"system\"] {";
-    result += &light_mode_variables(&config).unwrap();
-    result += "}";
-    result += "}";
+    result += &rule(
+        "@media (prefers-color-scheme: light)",
+        rule("[data-mode=\"system\"]", light.to_string()),
+    );
 
     // System mode | dark variables
-    result += "@media (prefers-color-scheme: dark) {";
-    result += "[data-mode=\"system\"] {";
-    result += &dark_mode_variables(&config).unwrap();
-    result += "}";
-    result += "}";
+    result += &rule(
+        "@media (prefers-color-scheme: dark)",
+        rule("[data-mode=\"system\"]", dark.to_string()),
+    );
 
-    // Default html css settings
-    result += "html {";
-    result += "color: var(--text);";
-    result += "background-color: var(--background);";
-    result += "font-size: max(1rem, 0.833vw);";
-    result += "}";
+    result
+}
 
-    Ok(result)
+/// Returns the names of the variables the stylesheet declares for the colors of a theme,
+/// without `--`, leaving out the aliases of the semantic colors.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+///
+/// # Returns
+/// `Vec<String>` - The names of the variables, which are the same in every mode.
+pub fn color_variable_names(theme: &Theme) -> Vec<String> {
+    root_variables(theme)
+        .into_iter()
+        .chain(color_variables(
+            theme,
+            Mode::Light,
+            &theme.light,
+            &theme.scale.light,
+            Gamut::Srgb,
+        ))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Returns the names of the variables a semantic color declares, without `--`, one for each
+/// variable of the color it stands for. A theme color of the same name declares the same.
+///
+/// # Arguments
+/// * `theme` - The theme holding the semantic colors.
+/// * `name` - The name of the semantic color.
+///
+/// # Returns
+/// `Vec<String>` - The names of the variables, e.g. `primary` and `primary-on`.
+pub fn semantic_variable_names(theme: &Theme, name: &str) -> Vec<String> {
+    semantic_suffixes(theme)
+        .iter()
+        .map(|suffix| format!("{}{}", name, suffix))
+        .collect()
+}
+
+/// Generates the hue variable of every theme color, e.g. `--alpha: 0;`, declared once on the
+/// root as the hue is the same in every mode.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn root_variables(theme: &Theme) -> Declarations {
+    theme
+        .colors
+        .iter()
+        .map(|(name, theme_color)| {
+            let name = match theme.primary_variable {
+                PrimaryVariable::Hue => name.clone(),
+                PrimaryVariable::Color => format!("{}-hue", name),
+            };
+            (name, theme_color.hue().to_string())
+        })
+        .collect()
+}
+
+/// Formats declarations as CSS, e.g. `--alpha-color: #ffd3d9;`.
+fn declarations_css(declarations: &[(String, String)]) -> String {
+    declarations
+        .iter()
+        .map(|(name, value)| format!("--{}: {};", name, value))
+        .collect()
 }
 
-pub fn light_mode_variables(config: &Config) -> Result<String> {
+pub fn light_mode_variables(config: &Config, gamut: Gamut) -> Result<Declarations> {
     // Extract the UI configuration from the main config
     let ui_config = config
         .ui
         .clone()
         .context("UI configuration missing in Seyuna config")?;
 
-    // CSS result string
-    let mut result = String::new();
-
-    result += &format!(
-        "--background: oklch({} {} {});",
-        ui_config.theme.light.background.lightness,
-        ui_config.theme.light.background.chroma,
-        ui_config.theme.light.background.hue
-    );
-    result += &format!(
-        "--text: oklch({} {} {});",
-        ui_config.theme.light.text.lightness,
-        ui_config.theme.light.text.chroma,
-        ui_config.theme.light.text.hue
-    );
-    result += &format!("--chroma: {};", ui_config.theme.light.chroma,);
-    result += &format!("--lightness: {};", ui_config.theme.light.lightness,);
-
-    Ok(result)
+    Ok(mode_variables(
+        &ui_config.theme,
+        Mode::Light,
+        &ui_config.theme.light,
+        &ui_config.theme.scale.light,
+        gamut,
+    ))
 }
 
-pub fn dark_mode_variables(config: &Config) -> Result<String> {
+pub fn dark_mode_variables(config: &Config, gamut: Gamut) -> Result<Declarations> {
     // Extract the UI configuration from the main config
     let ui_config = config
         .ui
         .clone()
         .context("UI configuration missing in Seyuna config")?;
 
-    // CSS result string
-    let mut result = String::new();
+    Ok(mode_variables(
+        &ui_config.theme,
+        Mode::Dark,
+        &ui_config.theme.dark,
+        &ui_config.theme.scale.dark,
+        gamut,
+    ))
+}
 
-    result += &format!(
-        "--background: oklch({} {} {});",
-        ui_config.theme.dark.background.lightness,
-        ui_config.theme.dark.background.chroma,
-        ui_config.theme.dark.background.hue
-    );
-    result += &format!(
-        "--text: oklch({} {} {});",
-        ui_config.theme.dark.text.lightness,
-        ui_config.theme.dark.text.chroma,
-        ui_config.theme.dark.text.hue
+/// Generates the variables of one color mode. Both gamuts declare the same variables in the
+/// same order, so their values can be compared one by one.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+/// * `mode` - The light or dark mode.
+/// * `palette` - The palette of the mode.
+/// * `curve` - The scale curve of the mode.
+/// * `gamut` - The gamut of the colors; see [`color_variable`].
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn mode_variables(
+    theme: &Theme,
+    mode: Mode,
+    palette: &Palette,
+    curve: &ScaleCurve,
+    gamut: Gamut,
+) -> Declarations {
+    let mut result = color_variables(theme, mode, palette, curve, gamut);
+    // Aliases follow the colors they point to, whatever the gamut
+    result.extend(semantic_variables(theme, mode));
+    result
+}
+
+/// Generates the color variables of one color mode, without the semantic aliases.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+/// * `mode` - The light or dark mode.
+/// * `palette` - The palette of the mode.
+/// * `curve` - The scale curve of the mode.
+/// * `gamut` - The gamut of the colors; see [`color_variable`].
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn color_variables(
+    theme: &Theme,
+    mode: Mode,
+    palette: &Palette,
+    curve: &ScaleCurve,
+    gamut: Gamut,
+) -> Declarations {
+    let mut result = Declarations::new();
+
+    let color = |color: &Color| Oklch::from_numbers(&color.lightness, &color.chroma, &color.hue);
+    result.push(color_variable(
+        "background",
+        color(&palette.background),
+        gamut,
+    ));
+    result.push(color_variable("text", color(&palette.text), gamut));
+    result.push(("chroma".to_string(), palette.chroma.to_string()));
+    result.push(("lightness".to_string(), palette.lightness.to_string()));
+    result.extend(theme_color_variables(theme, mode, palette, gamut));
+    result.extend(on_color_variables(theme, mode, palette, gamut));
+    result.extend(theme_scale_variables(theme, mode, palette, curve, gamut));
+    result.extend(neutral_variables(theme, palette, curve, gamut));
+
+    result
+}
+
+/// Generates the declaration of a color variable for a gamut: a hex color mapped into sRGB,
+/// or for Display P3 the `oklch()` color mapped into Display P3 if sRGB cannot show it.
+///
+/// # Arguments
+/// * `name` - The name of the variable, without `--`.
+/// * `color` - The configured color.
+/// * `gamut` - The gamut to map the color into.
+///
+/// # Returns
+/// `(String, String)` - The name and the value of the variable; the hex color for Display P3
+/// too if sRGB can show the color.
+fn color_variable(name: &str, color: Oklch, gamut: Gamut) -> (String, String) {
+    let value = match gamut {
+        Gamut::DisplayP3 if !color.in_gamut(Gamut::Srgb) => color.map_to_gamut(gamut).to_css(),
+        _ => color.to_hex(),
+    };
+    (name.to_string(), value)
+}
+
+/// Generates the color variables of every theme color for one mode, e.g.
+/// `--alpha-color: #ffd3d9;`, so they switch along with the mode.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+/// * `mode` - The light or dark mode.
+/// * `palette` - The palette of the mode.
+/// * `gamut` - The gamut of the colors; see [`color_variable`].
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn theme_color_variables(
+    theme: &Theme,
+    mode: Mode,
+    palette: &Palette,
+    gamut: Gamut,
+) -> Declarations {
+    theme
+        .colors
+        .iter()
+        .map(|(name, theme_color)| {
+            let color = theme_color.color(mode, palette);
+            match theme.primary_variable {
+                PrimaryVariable::Hue => color_variable(&format!("{}-color", name), color, gamut),
+                PrimaryVariable::Color => color_variable(name, color, gamut),
+            }
+        })
+        .collect()
+}
+
+/// Generates the aliases of every semantic color for one mode, one for each variable of the
+/// color it stands for, e.g. `--primary-color: var(--omicron-color);`. They are declared
+/// along with the colors, so they follow the mode of the element.
+///
+/// # Arguments
+/// * `theme` - The theme holding the semantic colors.
+/// * `mode` - The light or dark mode.
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn semantic_variables(theme: &Theme, mode: Mode) -> Declarations {
+    let suffixes = semantic_suffixes(theme);
+    let mut result = Declarations::new();
+    for (name, semantic) in &theme.semantic {
+        for suffix in &suffixes {
+            result.push((
+                format!("{}{}", name, suffix),
+                format!("var(--{}{})", semantic.target(mode), suffix),
+            ));
+        }
+    }
+    result
+}
+
+/// Returns the suffixes of the variables of a theme color, which its semantic colors alias:
+/// none, `-color` or `-hue`, `-on` if on-colors are generated, and every shade.
+fn semantic_suffixes(theme: &Theme) -> Vec<String> {
+    let mut suffixes = vec![
+        String::new(),
+        match theme.primary_variable {
+            PrimaryVariable::Hue => "-color".to_string(),
+            PrimaryVariable::Color => "-hue".to_string(),
+        },
+    ];
+    if theme.on_colors.enabled() {
+        suffixes.push("-on".to_string());
+    }
+    suffixes.extend(
+        theme
+            .scale
+            .shades()
+            .iter()
+            .map(|shade| format!("-{}", shade)),
     );
-    result += &format!("--chroma: {};", ui_config.theme.dark.chroma,);
-    result += &format!("--lightness: {};", ui_config.theme.dark.lightness,);
+    suffixes
+}
 
-    Ok(result)
+/// Generates the foreground of every theme color for one mode, e.g. `--alpha-on: #3b0b12;`,
+/// meeting the contrast of `theme.on_colors` against the color in the mode.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+/// * `mode` - The light or dark mode.
+/// * `palette` - The palette of the mode.
+/// * `gamut` - The gamut of the colors; see [`color_variable`].
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn on_color_variables(theme: &Theme, mode: Mode, palette: &Palette, gamut: Gamut) -> Declarations {
+    if !theme.on_colors.enabled() {
+        return Declarations::new();
+    }
+    theme
+        .colors
+        .iter()
+        .map(|(name, theme_color)| {
+            let foreground = theme
+                .on_colors
+                .foreground(theme_color.color(mode, palette), gamut);
+            color_variable(&format!("{}-on", name), foreground, gamut)
+        })
+        .collect()
+}
+
+/// Generates the shades of every theme color for one mode, e.g. `--alpha-100: #ffe4e6;`, so
+/// they switch along with the mode.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+/// * `mode` - The light or dark mode.
+/// * `palette` - The palette of the mode.
+/// * `curve` - The scale curve of the mode.
+/// * `gamut` - The gamut of the colors; see [`color_variable`].
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn theme_scale_variables(
+    theme: &Theme,
+    mode: Mode,
+    palette: &Palette,
+    curve: &ScaleCurve,
+    gamut: Gamut,
+) -> Declarations {
+    let shades = theme.scale.shades();
+    let mut result = Declarations::new();
+    for (name, theme_color) in &theme.colors {
+        let chroma = theme_color
+            .tone(mode, palette)
+            .0
+            .as_f64()
+            .unwrap_or_default();
+        let hue = theme_color.hue().as_f64().unwrap_or_default();
+        for &shade in &shades {
+            let (chroma, lightness) = curve.tone(shade, chroma);
+            result.push(color_variable(
+                &format!("{}-{}", name, shade),
+                Oklch::new(lightness, chroma, hue),
+                gamut,
+            ));
+        }
+    }
+    result
+}
+
+/// Generates the neutral shades and the surface levels for one mode, e.g.
+/// `--neutral-100: #e4e4e4;` and `--surface-1: #f1f1f1;`.
+///
+/// # Arguments
+/// * `theme` - The theme holding the neutral settings.
+/// * `palette` - The palette of the mode, whose background and text the surfaces step between.
+/// * `curve` - The scale curve of the mode.
+/// * `gamut` - The gamut of the colors; see [`color_variable`].
+///
+/// # Returns
+/// `Declarations` - The CSS declarations.
+fn neutral_variables(
+    theme: &Theme,
+    palette: &Palette,
+    curve: &ScaleCurve,
+    gamut: Gamut,
+) -> Declarations {
+    let (tint_chroma, tint_hue) = theme.neutral.tint();
+    let mut result = Declarations::new();
+
+    for shade in theme.scale.shades() {
+        let (chroma, lightness) = curve.tone(shade, tint_chroma);
+        result.push(color_variable(
+            &format!("neutral-{}", shade),
+            Oklch::new(lightness, chroma, tint_hue),
+            gamut,
+        ));
+    }
+
+    // Surfaces step from the background toward the text, tinted like the neutral shades. The
+    // steps are taken in perceived lightness, so they are as visible near black as near white.
+    let component = |value: &Number| value.as_f64().unwrap_or_default();
+    let step = component(&theme.neutral.surface_step);
+    let perceived = |color: &Color| perceived_lightness(component(&color.lightness));
+    for level in (1..=5).filter(|_| step > 0.0) {
+        let position = step * f64::from(level);
+        let mix = |from: f64, to: f64| from + (to - from) * position;
+        let lightness = oklch_lightness(mix(
+            perceived(&palette.background),
+            perceived(&palette.text),
+        ));
+        let (chroma, hue) = match theme.neutral.hue {
+            Some(_) => (tint_chroma, tint_hue),
+            None => (
+                mix(
+                    component(&palette.background.chroma),
+                    component(&palette.text.chroma),
+                ),
+                component(&palette.background.hue),
+            ),
+        };
+        result.push(color_variable(
+            &format!("surface-{}", level),
+            Oklch::new(lightness, chroma, hue),
+            gamut,
+        ));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::LoadOptions;
+    use crate::helpers::temp::TempDir;
+    use crate::ui::default::UI_CONFIGURATION;
+    use indexmap::IndexMap;
+
+    /// Loads a configuration file written to a temporary directory.
+    async fn load(directory: &TempDir, content: &str) -> LoadedConfig {
+        let options = LoadOptions {
+            config_path: Some(directory.write("seyuna.json", content)),
+            ..LoadOptions::default()
+        };
+        load_seyuna_user_config(&options).await.unwrap()
+    }
+
+    /// Returns the variables a configuration declares in one mode for sRGB displays, by name.
+    async fn variables(content: &str, mode: Mode) -> IndexMap<String, String> {
+        let loaded = load(&TempDir::new(), content).await;
+        let declarations = match mode {
+            Mode::Dark => dark_mode_variables(&loaded.config, Gamut::Srgb),
+            _ => light_mode_variables(&loaded.config, Gamut::Srgb),
+        };
+        declarations.unwrap().into_iter().collect()
+    }
+
+    /// Returns the sRGB channels of a hex color, e.g. `[255, 211, 217]` for `#ffd3d9`.
+    fn channels(hex: &str) -> [u8; 3] {
+        assert!(hex.starts_with('#') && hex.len() == 7, "{}", hex);
+        [1, 3, 5].map(|start| u8::from_str_radix(&hex[start..start + 2], 16).unwrap())
+    }
+
+    /// Returns the variables a configuration compiles to, before minifying.
+    async fn css(content: &str) -> String {
+        let loaded = load(&TempDir::new(), content).await;
+        css_variables(&loaded.config).unwrap()
+    }
+
+    /// Returns the body of the first rule with the selector, e.g. `:root`, with its nested rules.
+    fn rule<'a>(css: &'a str, selector: &str) -> Option<&'a str> {
+        let start = css.find(&format!("{} {{", selector))? + selector.len() + 2;
+        let mut depth = 1;
+        for (index, character) in css[start..].char_indices() {
+            match character {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(&css[start..start + index]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Returns the value a rule body declares for a variable, without `--`.
+    fn declaration<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+        body.split(';')
+            .find_map(|declaration| declaration.strip_prefix(&format!("--{}: ", name)))
+    }
+
+    /// Compiles a configuration file written to a temporary directory, returning the names of
+    /// the files in its output directory.
+    async fn compile(directory: &TempDir, content: &str) -> Vec<String> {
+        let loaded = load(directory, content).await;
+        write_stylesheet(&loaded).await.unwrap();
+        let mut names: Vec<String> = std::fs::read_dir(loaded.output_dir().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn outdated_hashed_files_are_removed() {
+        let directory = TempDir::new();
+        let hashed =
+            r#"{ "ui": { "output_dir": "dist", "build": { "hash": true, "source_map": true } } }"#;
+        let first = compile(&directory, hashed).await;
+        assert_eq!(first.len(), 3);
+
+        let changed = hashed.replace(
+            "\"dist\"",
+            "\"dist\", \"theme\": { \"colors\": { \"brand\": 120 } }",
+        );
+        let second = compile(&directory, &changed).await;
+        assert_eq!(second.len(), 3);
+        assert!(
+            second
+                .iter()
+                .all(|name| !first.contains(name) || name == MANIFEST_FILE_NAME)
+        );
+
+        let plain = compile(&directory, r#"{ "ui": { "output_dir": "dist" } }"#).await;
+        assert_eq!(plain, [STYLESHEET_FILE_NAME, MANIFEST_FILE_NAME]);
+    }
+
+    #[tokio::test]
+    async fn unlisted_files_are_kept() {
+        let directory = TempDir::new();
+        directory.write("dist/seyuna-global.vendor.css", "");
+        directory.write("dist/seyuna-global.vendor.css.map", "");
+        let content = r#"{ "ui": { "output_dir": "dist", "build": { "hash": true } } }"#;
+        compile(&directory, content).await;
+
+        let names = compile(&directory, r#"{ "ui": { "output_dir": "dist" } }"#).await;
+        assert_eq!(
+            names,
+            [
+                STYLESHEET_FILE_NAME,
+                "seyuna-global.vendor.css",
+                "seyuna-global.vendor.css.map",
+                MANIFEST_FILE_NAME,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_cannot_remove_files_outside_the_output_dir() {
+        let directory = TempDir::new();
+        let outside = directory.write("keep.css", "");
+        directory.write(
+            &format!("dist/{}", MANIFEST_FILE_NAME),
+            r#"{ "seyuna-global.css": "../keep.css" }"#,
+        );
+        compile(&directory, r#"{ "ui": { "output_dir": "dist" } }"#).await;
+
+        assert!(outside.exists());
+    }
+
+    #[tokio::test]
+    async fn neutral_scale_is_gray_without_a_tint() {
+        let content = r#"{ "ui": { "theme": { "scale": { "steps": 11 } } } }"#;
+        for mode in [Mode::Light, Mode::Dark] {
+            let variables = variables(content, mode).await;
+            let neutrals: Vec<[u8; 3]> = [50, 100, 200, 300, 400, 500, 600, 700, 800, 900, 950]
+                .iter()
+                .map(|shade| channels(&variables[&format!("neutral-{}", shade)]))
+                .collect();
+            assert!(
+                neutrals
+                    .iter()
+                    .all(|[red, green, blue]| red == green && green == blue)
+            );
+            // Light shades are light in both modes, like the shades of theme colors
+            let ordered = neutrals.windows(2).all(|pair| match mode {
+                Mode::Dark => pair[0][0] < pair[1][0],
+                _ => pair[0][0] > pair[1][0],
+            });
+            assert!(ordered, "{:?}: {:?}", mode, neutrals);
+        }
+    }
+
+    #[tokio::test]
+    async fn tinted_neutral_scale_and_surfaces_take_the_hue() {
+        let content = r#"{ "ui": { "theme": {
+            "scale": { "steps": 11 },
+            "neutral": { "hue": 250, "chroma": 0.03, "surface_step": 0.05 }
+        } } }"#;
+        for mode in [Mode::Light, Mode::Dark] {
+            let variables = variables(content, mode).await;
+            for name in ["neutral-300", "neutral-500", "neutral-700", "surface-3"] {
+                let [red, _, blue] = channels(&variables[name]);
+                assert!(blue > red, "{:?} {}: {}", mode, name, variables[name]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn surfaces_are_visible_and_ordered_in_both_modes() {
+        let content = r#"{ "ui": { "theme": { "neutral": { "surface_step": 0.05 } } } }"#;
+        for mode in [Mode::Light, Mode::Dark] {
+            let variables = variables(content, mode).await;
+            let background = channels(&variables["background"])[0];
+            let mut levels = vec![background];
+            for level in 1..=5 {
+                let surface = &variables[&format!("surface-{}", level)];
+                let [red, green, blue] = channels(surface);
+                assert!(red == green && green == blue, "{}", surface);
+                levels.push(red);
+            }
+            // Each level steps away from the background, visibly, toward the text
+            let ordered = levels.windows(2).all(|pair| match mode {
+                Mode::Dark => pair[1] >= pair[0] + 4,
+                _ => pair[1] + 4 <= pair[0],
+            });
+            assert!(ordered, "{:?}: {:?}", mode, levels);
+        }
+    }
+
+    #[tokio::test]
+    async fn surfaces_follow_the_palette() {
+        let content = r#"{ "ui": { "theme": {
+            "neutral": { "surface_step": 0.2 },
+            "dark": { "background": { "lightness": 0.25 }, "text": { "lightness": 0.9 } }
+        } } }"#;
+        let variables = variables(content, Mode::Dark).await;
+        let text = channels(&variables["text"]);
+        assert_eq!(channels(&variables["surface-5"]), text);
+        assert!(channels(&variables["surface-1"])[0] > channels(&variables["background"])[0]);
+    }
+
+    #[tokio::test]
+    async fn root_gets_the_variables_of_the_configured_mode() {
+        let light = css(r#"{ "ui": { "mode": "light" } }"#).await;
+        let root = rule(&light, ":root").unwrap();
+        assert_eq!(declaration(root, "background"), Some("#ffffff"));
+        assert!(!light.contains("@media (prefers-color-scheme: dark) {:root"));
+
+        let dark = css(r#"{ "ui": { "mode": "dark" } }"#).await;
+        let root = rule(&dark, ":root").unwrap();
+        assert_eq!(declaration(root, "background"), Some("#000000"));
+        assert_eq!(declaration(root, "alpha"), Some("0"));
+        assert!(!dark.contains("@media (prefers-color-scheme: dark) {:root"));
+
+        // The system mode follows the preference of the user
+        let system = css(r#"{ "ui": { "mode": "system" } }"#).await;
+        let root = rule(&system, ":root").unwrap();
+        assert_eq!(declaration(root, "background"), Some("#ffffff"));
+        let preferred = rule(&system, "@media (prefers-color-scheme: dark)").unwrap();
+        let dark_root = rule(preferred, ":root").unwrap();
+        assert_eq!(declaration(dark_root, "background"), Some("#000000"));
+
+        // An explicit data-mode still wins over the default
+        for css in [light, dark, system] {
+            let light = rule(&css, "[data-mode=\"light\"]").unwrap();
+            assert_eq!(declaration(light, "background"), Some("#ffffff"));
+            let dark = rule(&css, "[data-mode=\"dark\"]").unwrap();
+            assert_eq!(declaration(dark, "background"), Some("#000000"));
+            assert!(css.contains("@media (prefers-color-scheme: dark) {[data-mode=\"system\"]"));
+        }
+    }
+
+    #[tokio::test]
+    async fn theme_colors_get_a_color_variable_per_mode() {
+        let css = css(r#"{ "ui": { "theme": { "dark": { "lightness": 0.5 } } } }"#).await;
+        assert_eq!(
+            declaration(rule(&css, ":root").unwrap(), "beta"),
+            Some("15")
+        );
+
+        let light = rule(&css, "[data-mode=\"light\"]").unwrap();
+        let dark = rule(&css, "[data-mode=\"dark\"]").unwrap();
+        assert_eq!(
+            declaration(light, "beta-color"),
+            Some(Oklch::new(0.9, 0.7, 15.0).to_hex().as_str())
+        );
+        assert_eq!(
+            declaration(dark, "beta-color"),
+            Some(Oklch::new(0.5, 0.7, 15.0).to_hex().as_str())
+        );
+        assert_eq!(declaration(light, "beta"), None);
+    }
+
+    #[tokio::test]
+    async fn primary_variable_can_be_the_color() {
+        let css = css(r#"{ "ui": { "theme": { "primary_variable": "color" } } }"#).await;
+        let root = rule(&css, ":root").unwrap();
+        assert_eq!(declaration(root, "beta-hue"), Some("15"));
+
+        let light = rule(&css, "[data-mode=\"light\"]").unwrap();
+        assert_eq!(
+            declaration(light, "beta"),
+            Some(Oklch::new(0.9, 0.7, 15.0).to_hex().as_str())
+        );
+        assert_eq!(declaration(light, "beta-color"), None);
+    }
+
+    #[tokio::test]
+    async fn theme_colors_override_chroma_and_lightness_per_mode() {
+        let css = css(r#"{ "ui": { "theme": { "colors": {
+            "brand": { "hue": 250, "light": { "chroma": 0.1, "lightness": 0.5 } },
+            "plain": 250
+        } } } }"#)
+        .await;
+        let light = rule(&css, "[data-mode=\"light\"]").unwrap();
+        let dark = rule(&css, "[data-mode=\"dark\"]").unwrap();
+
+        assert_eq!(
+            declaration(light, "brand-color"),
+            Some(Oklch::new(0.5, 0.1, 250.0).to_hex().as_str())
+        );
+        assert_ne!(
+            declaration(light, "brand-color"),
+            declaration(light, "plain-color")
+        );
+        // The dark mode keeps the values of the dark palette
+        assert_eq!(
+            declaration(dark, "brand-color"),
+            declaration(dark, "plain-color")
+        );
+        assert_eq!(
+            declaration(rule(&css, ":root").unwrap(), "brand"),
+            Some("250")
+        );
+    }
+
+    #[tokio::test]
+    async fn theme_colors_get_a_tonal_scale() {
+        let shades = [
+            "50", "100", "200", "300", "400", "500", "600", "700", "800", "900", "950",
+        ];
+        let css = css(r#"{ "ui": { "theme": {
+            "colors": { "ink": { "hue": 250, "light": { "chroma": 0 }, "dark": { "chroma": 0 } } },
+            "scale": { "steps": 11 }
+        } } }"#)
+        .await;
+
+        for mode in ["light", "dark"] {
+            let body = rule(&css, &format!("[data-mode=\"{}\"]", mode)).unwrap();
+            let scale: Vec<u8> = shades
+                .iter()
+                .map(|shade| channels(declaration(body, &format!("ink-{}", shade)).unwrap())[0])
+                .collect();
+            // Shades step away from the background: darker in light mode, lighter in dark mode
+            let ordered = scale.windows(2).all(|pair| match mode {
+                "dark" => pair[0] < pair[1],
+                _ => pair[0] > pair[1],
+            });
+            assert!(ordered, "{}: {:?}", mode, scale);
+            assert!(declaration(body, "alpha-500").is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn scale_steps_choose_the_shades() {
+        let three = css(r#"{ "ui": { "theme": { "scale": { "steps": 3 } } } }"#).await;
+        let light = rule(&three, "[data-mode=\"light\"]").unwrap();
+        for shade in ["50", "500", "950"] {
+            assert!(declaration(light, &format!("alpha-{}", shade)).is_some());
+            assert!(declaration(light, &format!("neutral-{}", shade)).is_some());
+        }
+        assert_eq!(declaration(light, "alpha-100"), None);
+
+        let none = css(r#"{ "ui": { "theme": { "scale": { "steps": 0 } } } }"#).await;
+        assert!(!none.contains("--alpha-50:"));
+        assert!(!none.contains("--neutral-50:"));
+    }
+
+    #[tokio::test]
+    async fn scales_neutrals_and_on_colors_are_opt_in() {
+        let plain = css("{}").await;
+        for variable in ["--alpha-50:", "--neutral-", "--surface-", "--alpha-on:"] {
+            assert!(!plain.contains(variable), "{}", variable);
+        }
+        let (stylesheet, _) = render_stylesheet(
+            &load(&TempDir::new(), "{}").await.config,
+            UI_CONFIGURATION.build,
+        )
+        .unwrap();
+        assert!(stylesheet.len() < 16 * 1024, "{} bytes", stylesheet.len());
+
+        let full = css(r#"{ "ui": { "theme": {
+            "scale": { "steps": 11 },
+            "neutral": { "surface_step": 0.05 },
+            "on_colors": { "contrast": 4.5 }
+        } } }"#)
+        .await;
+        for variable in [
+            "--alpha-50:",
+            "--neutral-500:",
+            "--surface-5:",
+            "--alpha-on:",
+        ] {
+            assert!(full.contains(variable), "{}", variable);
+        }
+    }
+
+    #[tokio::test]
+    async fn colors_outside_srgb_are_upgraded_on_p3_displays() {
+        let css = css("{}").await;
+        let (srgb, _) = css.split_once("@media (color-gamut: p3)").unwrap();
+        assert!(!srgb.contains("oklch("));
+        assert!(
+            declaration(rule(srgb, "[data-mode=\"light\"]").unwrap(), "alpha-color")
+                .unwrap()
+                .starts_with('#')
+        );
+
+        let p3 = rule(&css, "@media (color-gamut: p3)").unwrap();
+        let p3 = rule(p3, "@supports (color: oklch(0 0 0))").unwrap();
+        let names = |body: &str| -> Vec<String> {
+            body.split(';')
+                .filter_map(|declaration| declaration.strip_prefix("--"))
+                .map(|declaration| declaration.split(':').next().unwrap().to_string())
+                .collect()
+        };
+        let light = rule(p3, "[data-mode=\"light\"]").unwrap();
+        let dark = rule(p3, "[data-mode=\"dark\"]").unwrap();
+        assert!(
+            declaration(light, "alpha-color")
+                .unwrap()
+                .starts_with("oklch(")
+        );
+        // Each mode redeclares every upgraded variable, so no mode inherits those of another
+        assert_eq!(names(light), names(dark));
+        assert_eq!(names(rule(p3, ":root").unwrap()), names(light));
+        assert_eq!(declaration(light, "background"), None);
+    }
+
+    #[tokio::test]
+    async fn colors_within_srgb_are_not_upgraded() {
+        let css = css(r#"{ "ui": { "theme": {
+            "colors": { "$replace": true, "ink": 250 },
+            "light": { "chroma": 0.01, "lightness": 0.5 },
+            "dark": { "chroma": 0.01, "lightness": 0.8 },
+            "on_colors": { "chroma": 0 }
+        } } }"#)
+        .await;
+        assert!(!css.contains("@media (color-gamut: p3)"));
+        assert!(!css.contains("oklch("));
+    }
+
+    /// Returns the WCAG 2 contrast ratio between two hex colors.
+    fn hex_contrast(first: &str, second: &str) -> f64 {
+        let luminance = |hex: &str| {
+            let [red, green, blue] = channels(hex).map(|channel| {
+                let channel = f64::from(channel) / 255.0;
+                if channel <= 0.04045 {
+                    channel / 12.92
+                } else {
+                    ((channel + 0.055) / 1.055).powf(2.4)
+                }
+            });
+            0.2126 * red + 0.7152 * green + 0.0722 * blue
+        };
+        let (first, second) = (luminance(first), luminance(second));
+        (first.max(second) + 0.05) / (first.min(second) + 0.05)
+    }
+
+    #[tokio::test]
+    async fn on_colors_meet_the_contrast_on_their_color() {
+        for (content, contrast) in [
+            (
+                r#"{ "ui": { "theme": { "on_colors": { "contrast": 4.5 } } } }"#,
+                4.5,
+            ),
+            (
+                r#"{ "ui": { "theme": { "on_colors": { "contrast": 7 } } } }"#,
+                7.0,
+            ),
+        ] {
+            let css = css(content).await;
+            for mode in ["light", "dark"] {
+                let body = rule(&css, &format!("[data-mode=\"{}\"]", mode)).unwrap();
+                for name in UI_CONFIGURATION.theme.colors.keys() {
+                    let color = declaration(body, &format!("{}-color", name)).unwrap();
+                    let on = declaration(body, &format!("{}-on", name)).unwrap();
+                    let ratio = hex_contrast(on, color);
+                    assert!(ratio >= contrast, "{} {}: {} on {}", mode, name, on, color);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn semantic_colors_alias_their_colors_per_mode() {
+        let css = css(r#"{ "ui": { "theme": {
+            "scale": { "steps": 11 },
+            "on_colors": { "contrast": 4.5 },
+            "semantic": {
+                "primary": "omicron",
+                "danger": { "light": "alpha", "dark": "beta" },
+                "accent": "primary"
+            }
+        } } }"#)
+        .await;
+        let light = rule(&css, "[data-mode=\"light\"]").unwrap();
+        let dark = rule(&css, "[data-mode=\"dark\"]").unwrap();
+
+        for (suffix, body) in [
+            ("", light),
+            ("-color", light),
+            ("-on", dark),
+            ("-500", dark),
+        ] {
+            assert_eq!(
+                declaration(body, &format!("primary{}", suffix)),
+                Some(format!("var(--omicron{})", suffix).as_str())
+            );
+        }
+        assert_eq!(
+            declaration(light, "danger-color"),
+            Some("var(--alpha-color)")
+        );
+        assert_eq!(declaration(dark, "danger-color"), Some("var(--beta-color)"));
+        // An alias of an alias points to the alias, which resolves in the same element
+        assert_eq!(
+            declaration(light, "accent-color"),
+            Some("var(--primary-color)")
+        );
+        // Aliases are the same on every display, so they are not redeclared for P3
+        let p3 = rule(&css, "@media (color-gamut: p3)").unwrap();
+        assert!(!p3.contains("--primary"));
+    }
 }
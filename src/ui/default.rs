@@ -9,30 +9,63 @@ pub static UI_CONFIGURATION: Lazy<types::UI> = Lazy::new(|| types::UI {
     slogan: String::from("Another cool Seyuna app."),
     theme: types::Theme {
         colors: IndexMap::from([
-            ("alpha".to_string(), Number::from(0)),
-            ("beta".to_string(), Number::from(15)),
-            ("gamma".to_string(), Number::from(30)),
-            ("delta".to_string(), Number::from(45)),
-            ("epsilon".to_string(), Number::from(60)),
-            ("zeta".to_string(), Number::from(75)),
-            ("eta".to_string(), Number::from(90)),
-            ("theta".to_string(), Number::from(105)),
-            ("iota".to_string(), Number::from(120)),
-            ("kappa".to_string(), Number::from(135)),
-            ("lambda".to_string(), Number::from(150)),
-            ("mu".to_string(), Number::from(165)),
-            ("nu".to_string(), Number::from(180)),
-            ("xi".to_string(), Number::from(195)),
-            ("omicron".to_string(), Number::from(210)),
-            ("pi".to_string(), Number::from(225)),
-            ("rho".to_string(), Number::from(240)),
-            ("sigma".to_string(), Number::from(255)),
-            ("tau".to_string(), Number::from(270)),
-            ("upsilon".to_string(), Number::from(285)),
-            ("phi".to_string(), Number::from(300)),
-            ("chi".to_string(), Number::from(315)),
-            ("psi".to_string(), Number::from(330)),
-            ("omega".to_string(), Number::from(345)),
+            ("alpha".to_string(), types::ThemeColor::Hue(Number::from(0))),
+            ("beta".to_string(), types::ThemeColor::Hue(Number::from(15))),
+            (
+                "gamma".to_string(),
+                types::ThemeColor::Hue(Number::from(30)),
+            ),
+            (
+                "delta".to_string(),
+                types::ThemeColor::Hue(Number::from(45)),
+            ),
+            (
+                "epsilon".to_string(),
+                types::ThemeColor::Hue(Number::from(60)),
+            ),
+            ("zeta".to_string(), types::ThemeColor::Hue(Number::from(75))),
+            ("eta".to_string(), types::ThemeColor::Hue(Number::from(90))),
+            (
+                "theta".to_string(),
+                types::ThemeColor::Hue(Number::from(105)),
+            ),
+            (
+                "iota".to_string(),
+                types::ThemeColor::Hue(Number::from(120)),
+            ),
+            (
+                "kappa".to_string(),
+                types::ThemeColor::Hue(Number::from(135)),
+            ),
+            (
+                "lambda".to_string(),
+                types::ThemeColor::Hue(Number::from(150)),
+            ),
+            ("mu".to_string(), types::ThemeColor::Hue(Number::from(165))),
+            ("nu".to_string(), types::ThemeColor::Hue(Number::from(180))),
+            ("xi".to_string(), types::ThemeColor::Hue(Number::from(195))),
+            (
+                "omicron".to_string(),
+                types::ThemeColor::Hue(Number::from(210)),
+            ),
+            ("pi".to_string(), types::ThemeColor::Hue(Number::from(225))),
+            ("rho".to_string(), types::ThemeColor::Hue(Number::from(240))),
+            (
+                "sigma".to_string(),
+                types::ThemeColor::Hue(Number::from(255)),
+            ),
+            ("tau".to_string(), types::ThemeColor::Hue(Number::from(270))),
+            (
+                "upsilon".to_string(),
+                types::ThemeColor::Hue(Number::from(285)),
+            ),
+            ("phi".to_string(), types::ThemeColor::Hue(Number::from(300))),
+            ("chi".to_string(), types::ThemeColor::Hue(Number::from(315))),
+            ("psi".to_string(), types::ThemeColor::Hue(Number::from(330))),
+            (
+                "omega".to_string(),
+                types::ThemeColor::Hue(Number::from(345)),
+            ),
         ]),
         light: types::Palette {
             chroma: Number::from_f64(0.70).unwrap(),
@@ -62,7 +95,38 @@ pub static UI_CONFIGURATION: Lazy<types::UI> = Lazy::new(|| types::UI {
                 lightness: Number::from_f64(1.0).unwrap(),
             },
         },
+        primary_variable: types::PrimaryVariable::Hue,
+        scale: types::Scale {
+            steps: 0,
+            light: types::ScaleCurve {
+                start: Number::from_f64(0.97).unwrap(),
+                end: Number::from_f64(0.28).unwrap(),
+                curve: Number::from_f64(1.0).unwrap(),
+                chroma_falloff: Number::from_f64(0.6).unwrap(),
+            },
+            dark: types::ScaleCurve {
+                start: Number::from_f64(0.2).unwrap(),
+                end: Number::from_f64(0.96).unwrap(),
+                curve: Number::from_f64(1.0).unwrap(),
+                chroma_falloff: Number::from_f64(0.6).unwrap(),
+            },
+        },
+        neutral: types::Neutral {
+            hue: None,
+            chroma: Number::from_f64(0.01).unwrap(),
+            surface_step: Number::from(0),
+        },
+        on_colors: types::OnColors {
+            contrast: Number::from(0),
+            chroma: Number::from_f64(0.02).unwrap(),
+        },
+        semantic: IndexMap::new(),
     },
     mode: types::Mode::System,
     output_dir: Some(String::from("styles")),
+    build: types::Build {
+        minify: true,
+        source_map: false,
+        hash: false,
+    },
 });
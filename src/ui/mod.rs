@@ -2,17 +2,29 @@ use clap::ArgMatches;
 
 use crate::helpers::cli::error_text;
 
+pub mod audit;
+pub mod color;
 pub mod compile;
 pub mod default;
 pub mod types;
+pub mod validate;
+pub mod workspace;
 
 pub async fn handle_ui_args(matches: ArgMatches) {
-    if let Some(ui_matches) = matches.subcommand_matches("ui") {
-        if ui_matches.get_flag("compile") {
-            if let Err(e) = compile::compile(ui_matches).await {
-                eprintln!("{}", error_text(&e.to_string()));
-                std::process::exit(1); // exit with error
-            }
-        }
+    let Some(ui_matches) = matches.subcommand_matches("ui") else {
+        return;
+    };
+
+    let result = if let Some(audit_matches) = ui_matches.subcommand_matches("audit") {
+        audit::audit(audit_matches).await
+    } else if ui_matches.get_flag("compile") {
+        compile::compile(ui_matches).await.map(|_| ())
+    } else {
+        Ok(())
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", error_text(&format!("{:#}", e)));
+        std::process::exit(1); // exit with error
     }
 }
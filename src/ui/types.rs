@@ -3,20 +3,51 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Number;
 
+use crate::{
+    config::patch::{MapPatch, nullable},
+    ui::{
+        color::{Gamut, Oklch, on_color},
+        default::UI_CONFIGURATION,
+    },
+};
+
+/// The Seyuna UI configuration.
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct UI {
+    /// The name of the app.
     pub name: String,
+    /// A short description of the app.
     pub slogan: String,
+    /// The colors of the app.
     pub theme: Theme,
+    /// The color mode used when the page does not set one.
     pub mode: Mode,
+    /// The directory the stylesheet is compiled to, relative to the configuration file.
     pub output_dir: Option<String>,
+    /// How the compiled stylesheet is written.
+    pub build: Build,
 }
 
+/// How the compiled stylesheet is written.
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy)]
+pub struct Build {
+    /// Minify the stylesheet.
+    pub minify: bool,
+    /// Write a source map next to the stylesheet.
+    pub source_map: bool,
+    /// Add a content hash to the file name. `seyuna-manifest.json` lists the file name either way.
+    pub hash: bool,
+}
+
+/// A color mode.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Mode {
+    /// Follow the light or dark preference of the operating system.
     System,
+    /// Always light.
     Light,
+    /// Always dark.
     Dark,
 }
 
@@ -24,81 +55,779 @@ pub type Hue = Number;
 pub type Chroma = Number;
 pub type Lightness = Number;
 
+/// An OKLCH color.
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Color {
+    /// The hue angle, in degrees.
     pub hue: Hue,
+    /// The colorfulness, from `0` (gray) upwards.
     pub chroma: Chroma,
+    /// The perceived lightness, from `0` (black) to `1` (white).
     pub lightness: Lightness,
 }
 
+/// The colors of one color mode.
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Palette {
+    /// The chroma shared by every theme color.
     pub chroma: Chroma,
+    /// The lightness shared by every theme color.
     pub lightness: Lightness,
+    /// The page background.
     pub background: Color,
+    /// The body text.
     pub text: Color,
 }
 
+/// The colors of the app.
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Theme {
-    pub colors: IndexMap<String, Hue>,
+    /// Named theme colors, compiled to CSS variables.
+    pub colors: IndexMap<String, ThemeColor>,
+    /// The palette of the light mode.
     pub light: Palette,
+    /// The palette of the dark mode.
     pub dark: Palette,
+    /// Which CSS variable of a theme color gets its plain name, e.g. `--alpha`.
+    pub primary_variable: PrimaryVariable,
+    /// The shades generated for every theme color, e.g. `--alpha-50` to `--alpha-950`.
+    pub scale: Scale,
+    /// The gray shades and surface levels, e.g. `--neutral-100` and `--surface-1`.
+    pub neutral: Neutral,
+    /// The foreground colors generated for every theme color, e.g. `--alpha-on`.
+    pub on_colors: OnColors,
+    /// Names describing the role of theme colors, e.g. `primary` or `danger`.
+    pub semantic: IndexMap<String, SemanticColor>,
+}
+
+/// A name describing the role of a theme color, compiled to aliases of every variable of the
+/// color, e.g. `--primary-color: var(--omicron-color);`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(
+    untagged,
+    expecting = "a color name, or an object with the color names of the light and dark modes"
+)]
+pub enum SemanticColor {
+    /// The name of a theme color or of another semantic color.
+    Color(String),
+    /// A different color in each mode.
+    PerMode(ModeColors),
+}
+
+/// The names of the colors a semantic color stands for in each mode.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ModeColors {
+    /// The name of a theme color or of another semantic color, in the light mode.
+    #[schemars(example = &"omicron")]
+    pub light: String,
+    /// The name of a theme color or of another semantic color, in the dark mode.
+    #[schemars(example = &"xi")]
+    pub dark: String,
+}
+
+/// The foreground colors shown on theme colors, e.g. `--alpha-on` for text on `--alpha-color`.
+/// An on-color keeps the hue of its color and gets the lightness closest to it that meets the
+/// contrast, toward black or white, whichever contrasts more.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct OnColors {
+    /// The WCAG 2 contrast ratio every on-color meets against its color, from `1` to `21`,
+    /// e.g. `4.5` for AA or `7` for AAA, or `0` for no on-colors.
+    pub contrast: Number,
+    /// The chroma of the on-colors.
+    pub chroma: Chroma,
+}
+
+/// The gray shades, `--neutral-50` to `--neutral-950` along the [`Scale`], and the surface
+/// levels `--surface-1` to `--surface-5` stepping from the background toward the text.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct Neutral {
+    /// The hue tinting the grays, in degrees; pure gray if missing.
+    pub hue: Option<Hue>,
+    /// The chroma of the tint, usually tiny, e.g. `0.01`.
+    pub chroma: Chroma,
+    /// How far each surface level moves from the background toward the text in perceived
+    /// lightness, up to `0.2`, so `--surface-5` is at most the text color, or `0` for no
+    /// surface levels.
+    pub surface_step: Number,
+}
+
+/// The shades generated for every theme color, named like `50`, `100` to `900` and `950`.
+/// A shade has the same name in both modes, so `--alpha-100` is a subtle surface in light
+/// and dark mode alike.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct Scale {
+    /// The number of shades, from `2` to `11`, or `0` for none.
+    pub steps: u8,
+    /// The shades in the light mode.
+    pub light: ScaleCurve,
+    /// The shades in the dark mode.
+    pub dark: ScaleCurve,
+}
+
+/// How the shades of a scale change from `50` to `950` in one mode.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ScaleCurve {
+    /// The lightness of the `50` shade.
+    pub start: Lightness,
+    /// The lightness of the `950` shade.
+    pub end: Lightness,
+    /// The exponent bending the lightness from `start` to `end`: `1` changes it evenly, higher
+    /// values keep the first shades closer to `start`.
+    pub curve: Number,
+    /// How much the chroma fades toward the `50` and `950` shades, from `0` (not at all) to
+    /// `1` (gray at both ends).
+    pub chroma_falloff: Number,
+}
+
+/// A theme color: a hue, shown with the chroma and lightness of the palette of each mode, or a
+/// hue with its own chroma or lightness in some modes.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(
+    untagged,
+    expecting = "a hue, or an object with a hue and optional light and dark tones"
+)]
+pub enum ThemeColor {
+    /// The hue angle, in degrees.
+    Hue(#[schemars(range(min = 0, max = 360), example = 210)] Hue),
+    /// A hue with its own tones.
+    Custom(CustomColor),
+}
+
+/// A theme color with its own chroma or lightness in some modes.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CustomColor {
+    /// The hue angle, in degrees.
+    #[schemars(range(min = 0, max = 360), example = 90)]
+    pub hue: Hue,
+    /// The tone in the light mode; missing values are taken from the light palette.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub light: Option<Tone>,
+    /// The tone in the dark mode; missing values are taken from the dark palette.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dark: Option<Tone>,
+}
+
+/// The chroma and lightness of a theme color in one mode.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Tone {
+    /// The colorfulness, from `0` (gray) upwards. Values above `0.4` cannot be displayed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0), example = 0.18)]
+    pub chroma: Option<Chroma>,
+    /// The perceived lightness, from `0` (black) to `1` (white).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 1), example = 0.85)]
+    pub lightness: Option<Lightness>,
+}
+
+/// The CSS variables of a theme color that can get its plain name.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PrimaryVariable {
+    /// `--alpha` is the hue, and `--alpha-color` the color of the current mode.
+    Hue,
+    /// `--alpha` is the color of the current mode, and `--alpha-hue` the hue.
+    Color,
+}
+
+/// A user-provided [`UI`] where every field is optional.
+/// Missing fields keep the value of the configuration it is merged over.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialUI {
+    /// The name of the app.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.name), example = &"Acme")]
+    pub name: Option<String>,
+    /// A short description of the app.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.slogan))]
+    pub slogan: Option<String>,
+    /// The colors of the app.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<PartialTheme>,
+    /// The color mode used when the page does not set one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.mode))]
+    pub mode: Option<Mode>,
+    /// The directory the stylesheet is compiled to, relative to the configuration file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.output_dir), example = &"src/styles")]
+    pub output_dir: Option<String>,
+    /// How the compiled stylesheet is written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<PartialBuild>,
+}
+
+/// A user-provided [`Build`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialBuild {
+    /// Minify the stylesheet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.build.minify))]
+    pub minify: Option<bool>,
+    /// Write a source map next to the stylesheet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.build.source_map))]
+    pub source_map: Option<bool>,
+    /// Add a content hash to the file name. `seyuna-manifest.json` lists the file name either way.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.build.hash))]
+    pub hash: Option<bool>,
+}
+
+/// A user-provided [`Color`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialColor {
+    /// The hue angle, in degrees.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 360), example = 210)]
+    pub hue: Option<Hue>,
+    /// The colorfulness, from `0` (gray) upwards. Values above `0.4` cannot be displayed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0), example = 0.02)]
+    pub chroma: Option<Chroma>,
+    /// The perceived lightness, from `0` (black) to `1` (white).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 1), example = 0.95)]
+    pub lightness: Option<Lightness>,
+}
+
+/// A user-provided [`Palette`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialPalette {
+    /// The chroma shared by every theme color. Values above `0.4` cannot be displayed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0), example = 0.15)]
+    pub chroma: Option<Chroma>,
+    /// The lightness shared by every theme color, from `0` (black) to `1` (white).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 1), example = 0.7)]
+    pub lightness: Option<Lightness>,
+    /// The page background.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<PartialColor>,
+    /// The body text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<PartialColor>,
+}
+
+/// A user-provided [`Theme`] where every field is optional.
+/// `colors` extends the inherited colors; see [`MapPatch`] for removing or replacing them.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialTheme {
+    /// Named theme colors, compiled to CSS variables: a hue in degrees, or an object with a
+    /// hue and its own chroma or lightness per mode.
+    /// Extends the inherited colors: set a color to `null` to remove it, or add
+    /// `"$replace": true` to start from no colors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        extend("default" = UI_CONFIGURATION.theme.colors),
+        example = serde_json::json!({
+            "brand": 210,
+            "yellow": { "hue": 90, "light": { "lightness": 0.8 }, "dark": { "chroma": 0.15 } }
+        })
+    )]
+    pub colors: Option<MapPatch<ThemeColor>>,
+    /// The palette of the light mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.theme.light))]
+    pub light: Option<PartialPalette>,
+    /// The palette of the dark mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.theme.dark))]
+    pub dark: Option<PartialPalette>,
+    /// Which CSS variable of a theme color gets its plain name, e.g. `--alpha`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.theme.primary_variable))]
+    pub primary_variable: Option<PrimaryVariable>,
+    /// The shades generated for every theme color, e.g. `--alpha-50` to `--alpha-950`; none
+    /// until `steps` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<PartialScale>,
+    /// The gray shades and surface levels, e.g. `--neutral-100` and `--surface-1`. The shades
+    /// follow `scale.steps`, and the surface levels need a `surface_step`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub neutral: Option<PartialNeutral>,
+    /// The foreground colors generated for every theme color, e.g. `--alpha-on`; none until
+    /// `contrast` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_colors: Option<PartialOnColors>,
+    /// Names describing the role of theme colors: the name of a theme color or of another
+    /// semantic color, or an object with one for each mode.
+    /// Extends the inherited names: set a name to `null` to remove it, or add
+    /// `"$replace": true` to start from no names.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        extend("default" = UI_CONFIGURATION.theme.semantic),
+        example = serde_json::json!({
+            "primary": "omicron",
+            "danger": "alpha",
+            "error": "danger",
+            "highlight": { "light": "omicron", "dark": "xi" }
+        })
+    )]
+    pub semantic: Option<MapPatch<SemanticColor>>,
+}
+
+/// A user-provided [`OnColors`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialOnColors {
+    /// The WCAG 2 contrast ratio every on-color meets against its color, from `1` to `21`,
+    /// e.g. `4.5` for AA or `7` for AAA, or `0` for no on-colors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        extend("default" = UI_CONFIGURATION.theme.on_colors.contrast),
+        range(min = 0, max = 21),
+        example = 7
+    )]
+    pub contrast: Option<Number>,
+    /// The chroma of the on-colors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        extend("default" = UI_CONFIGURATION.theme.on_colors.chroma),
+        range(min = 0),
+        example = 0.05
+    )]
+    pub chroma: Option<Chroma>,
+}
+
+/// A user-provided [`Neutral`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialNeutral {
+    /// The hue tinting the grays, in degrees; `null` makes inherited grays pure gray again.
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(range(min = 0, max = 360), example = 250)]
+    pub hue: Option<Option<Hue>>,
+    /// The chroma of the tint, usually tiny.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        extend("default" = UI_CONFIGURATION.theme.neutral.chroma),
+        range(min = 0),
+        example = 0.015
+    )]
+    pub chroma: Option<Chroma>,
+    /// How far each surface level moves from the background toward the text in perceived
+    /// lightness, up to `0.2`, or `0` for no surface levels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        extend("default" = UI_CONFIGURATION.theme.neutral.surface_step),
+        range(min = 0, max = 0.2),
+        example = 0.08
+    )]
+    pub surface_step: Option<Number>,
+}
+
+/// A user-provided [`Scale`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialScale {
+    /// The number of shades, from `2` to `11`, or `0` for none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.theme.scale.steps), range(max = 11))]
+    pub steps: Option<u8>,
+    /// The shades in the light mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.theme.scale.light))]
+    pub light: Option<PartialScaleCurve>,
+    /// The shades in the dark mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("default" = UI_CONFIGURATION.theme.scale.dark))]
+    pub dark: Option<PartialScaleCurve>,
+}
+
+/// A user-provided [`ScaleCurve`] where every field is optional.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PartialScaleCurve {
+    /// The lightness of the `50` shade, from `0` (black) to `1` (white).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 1), example = 0.97)]
+    pub start: Option<Lightness>,
+    /// The lightness of the `950` shade, from `0` (black) to `1` (white).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 1), example = 0.25)]
+    pub end: Option<Lightness>,
+    /// The exponent bending the lightness from `start` to `end`: `1` changes it evenly, higher
+    /// values keep the first shades closer to `start`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(example = 1.2)]
+    pub curve: Option<Number>,
+    /// How much the chroma fades toward the `50` and `950` shades, from `0` (not at all) to
+    /// `1` (gray at both ends).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 0, max = 1), example = 0.5)]
+    pub chroma_falloff: Option<Number>,
+}
+
+impl ThemeColor {
+    /// Returns the hue of the color.
+    pub fn hue(&self) -> &Hue {
+        match self {
+            Self::Hue(hue) => hue,
+            Self::Custom(color) => &color.hue,
+        }
+    }
+
+    /// Returns the chroma and lightness of the color in a mode.
+    ///
+    /// # Arguments
+    /// * `mode` - The light or dark mode.
+    /// * `palette` - The palette of the mode, for the values the color does not set.
+    ///
+    /// # Returns
+    /// `(&Chroma, &Lightness)` - The chroma and lightness.
+    pub fn tone<'a>(&'a self, mode: Mode, palette: &'a Palette) -> (&'a Chroma, &'a Lightness) {
+        let tone = match (self, mode) {
+            (Self::Custom(color), Mode::Light) => color.light.as_ref(),
+            (Self::Custom(color), Mode::Dark) => color.dark.as_ref(),
+            _ => None,
+        };
+        (
+            tone.and_then(|tone| tone.chroma.as_ref())
+                .unwrap_or(&palette.chroma),
+            tone.and_then(|tone| tone.lightness.as_ref())
+                .unwrap_or(&palette.lightness),
+        )
+    }
+
+    /// Returns the color in a mode, with the chroma and lightness of [`ThemeColor::tone`].
+    pub fn color(&self, mode: Mode, palette: &Palette) -> Oklch {
+        let (chroma, lightness) = self.tone(mode, palette);
+        Oklch::from_numbers(lightness, chroma, self.hue())
+    }
+}
+
+impl Scale {
+    /// Returns the names of the shades, e.g. `[50, 100, 200, ..., 900, 950]` for 11 steps.
+    /// The first and last shades are `50` and `950`, and the others are spread over the
+    /// hundreds in between.
+    pub fn shades(&self) -> Vec<u16> {
+        let between = u16::from(self.steps.saturating_sub(2)).min(9);
+        let mut shades = Vec::new();
+        if self.steps > 0 {
+            shades.push(50);
+        }
+        shades.extend((0..between).map(|index| match between {
+            1 => 500,
+            // Round to the nearest hundred
+            _ => 100 + (16 * index + between - 1) / (2 * (between - 1)) * 100,
+        }));
+        if self.steps > 1 {
+            shades.push(950);
+        }
+        shades
+    }
+}
+
+impl ScaleCurve {
+    /// Returns the chroma and lightness of a shade.
+    ///
+    /// # Arguments
+    /// * `shade` - The name of the shade, from `50` to `950`.
+    /// * `chroma` - The chroma of the theme color in the mode.
+    ///
+    /// # Returns
+    /// `(f64, f64)` - The chroma and lightness.
+    pub fn tone(&self, shade: u16, chroma: f64) -> (f64, f64) {
+        let position = (f64::from(shade) - 50.0) / 900.0;
+        let start = self.start.as_f64().unwrap_or_default();
+        let end = self.end.as_f64().unwrap_or_default();
+        let curve = self.curve.as_f64().unwrap_or(1.0);
+        let falloff = self.chroma_falloff.as_f64().unwrap_or_default();
+
+        let lightness = start + (end - start) * position.powf(curve);
+        // Fade the chroma toward both ends, where there is little room for color
+        let distance = 2.0 * position - 1.0;
+        (chroma * (1.0 - falloff * distance * distance), lightness)
+    }
+}
+
+impl Neutral {
+    /// Returns the chroma and hue of the tint; no chroma without a hue.
+    pub fn tint(&self) -> (f64, f64) {
+        match &self.hue {
+            Some(hue) => (
+                self.chroma.as_f64().unwrap_or_default(),
+                hue.as_f64().unwrap_or_default(),
+            ),
+            None => (0.0, 0.0),
+        }
+    }
+}
+
+impl SemanticColor {
+    /// Returns the name of the color the semantic color stands for in a mode.
+    pub fn target(&self, mode: Mode) -> &str {
+        match (self, mode) {
+            (Self::Color(name), _) => name,
+            (Self::PerMode(colors), Mode::Dark) => &colors.dark,
+            (Self::PerMode(colors), _) => &colors.light,
+        }
+    }
+}
+
+impl OnColors {
+    /// Returns whether on-colors are generated, which a contrast of `0` turns off.
+    pub fn enabled(&self) -> bool {
+        self.contrast.as_f64().unwrap_or_default() > 0.0
+    }
+
+    /// Returns the foreground meeting the contrast on a color, as both are shown on displays
+    /// with the gamut.
+    pub fn foreground(&self, color: Oklch, gamut: Gamut) -> Oklch {
+        on_color(
+            color,
+            self.contrast.as_f64().unwrap_or_default(),
+            self.chroma.as_f64().unwrap_or_default(),
+            gamut,
+        )
+    }
 }
 
 impl UI {
-    pub fn merge(self, other: Self) -> Self {
+    pub fn merge(self, other: PartialUI) -> Self {
         Self {
-            name: if other.name.is_empty() {
-                self.name
-            } else {
-                other.name
-            },
-            slogan: if other.slogan.is_empty() {
-                self.slogan
-            } else {
-                other.slogan
+            name: other
+                .name
+                .filter(|name| !name.is_empty())
+                .unwrap_or(self.name),
+            slogan: other
+                .slogan
+                .filter(|slogan| !slogan.is_empty())
+                .unwrap_or(self.slogan),
+            theme: match other.theme {
+                Some(theme) => self.theme.merge(theme),
+                None => self.theme,
             },
-            theme: self.theme.merge(other.theme),
-            mode: other.mode,
+            mode: other.mode.unwrap_or(self.mode),
             output_dir: other.output_dir.or(self.output_dir),
+            build: match other.build {
+                Some(build) => self.build.merge(build),
+                None => self.build,
+            },
+        }
+    }
+}
+
+impl Build {
+    pub fn merge(self, other: PartialBuild) -> Self {
+        Self {
+            minify: other.minify.unwrap_or(self.minify),
+            source_map: other.source_map.unwrap_or(self.source_map),
+            hash: other.hash.unwrap_or(self.hash),
         }
     }
 }
 
 impl Theme {
-    pub fn merge(self, other: Self) -> Self {
-        let mut merged_colors = self.colors;
+    pub fn merge(self, other: PartialTheme) -> Self {
+        Self {
+            colors: match other.colors {
+                Some(colors) => colors.apply(self.colors),
+                None => self.colors,
+            },
+            light: match other.light {
+                Some(light) => self.light.merge(light),
+                None => self.light,
+            },
+            dark: match other.dark {
+                Some(dark) => self.dark.merge(dark),
+                None => self.dark,
+            },
+            primary_variable: other.primary_variable.unwrap_or(self.primary_variable),
+            scale: match other.scale {
+                Some(scale) => self.scale.merge(scale),
+                None => self.scale,
+            },
+            neutral: match other.neutral {
+                Some(neutral) => self.neutral.merge(neutral),
+                None => self.neutral,
+            },
+            on_colors: match other.on_colors {
+                Some(on_colors) => self.on_colors.merge(on_colors),
+                None => self.on_colors,
+            },
+            semantic: match other.semantic {
+                Some(semantic) => semantic.apply(self.semantic),
+                None => self.semantic,
+            },
+        }
+    }
+}
+
+impl OnColors {
+    pub fn merge(self, other: PartialOnColors) -> Self {
+        Self {
+            contrast: other.contrast.unwrap_or(self.contrast),
+            chroma: other.chroma.unwrap_or(self.chroma),
+        }
+    }
+}
 
-        for (key, value) in other.colors {
-            merged_colors.insert(key, value);
+impl Neutral {
+    pub fn merge(self, other: PartialNeutral) -> Self {
+        Self {
+            hue: match other.hue {
+                Some(hue) => hue,
+                None => self.hue,
+            },
+            chroma: other.chroma.unwrap_or(self.chroma),
+            surface_step: other.surface_step.unwrap_or(self.surface_step),
+        }
+    }
+}
+
+impl Scale {
+    pub fn merge(self, other: PartialScale) -> Self {
+        Self {
+            steps: other.steps.unwrap_or(self.steps),
+            light: match other.light {
+                Some(light) => self.light.merge(light),
+                None => self.light,
+            },
+            dark: match other.dark {
+                Some(dark) => self.dark.merge(dark),
+                None => self.dark,
+            },
         }
+    }
+}
 
+impl ScaleCurve {
+    pub fn merge(self, other: PartialScaleCurve) -> Self {
         Self {
-            colors: merged_colors,
-            light: self.light.merge(other.light),
-            dark: self.dark.merge(other.dark),
+            start: other.start.unwrap_or(self.start),
+            end: other.end.unwrap_or(self.end),
+            curve: other.curve.unwrap_or(self.curve),
+            chroma_falloff: other.chroma_falloff.unwrap_or(self.chroma_falloff),
         }
     }
 }
 
 impl Palette {
-    pub fn merge(self, other: Self) -> Self {
+    pub fn merge(self, other: PartialPalette) -> Self {
         Self {
-            chroma: other.chroma,
-            lightness: other.lightness,
-            background: self.background.merge(other.background),
-            text: self.text.merge(other.text),
+            chroma: other.chroma.unwrap_or(self.chroma),
+            lightness: other.lightness.unwrap_or(self.lightness),
+            background: match other.background {
+                Some(background) => self.background.merge(background),
+                None => self.background,
+            },
+            text: match other.text {
+                Some(text) => self.text.merge(text),
+                None => self.text,
+            },
         }
     }
 }
 
 impl Color {
-    pub fn merge(self, other: Self) -> Self {
+    pub fn merge(self, other: PartialColor) -> Self {
         Self {
-            hue: other.hue,
-            chroma: other.chroma,
-            lightness: other.lightness,
+            hue: other.hue.unwrap_or(self.hue),
+            chroma: other.chroma.unwrap_or(self.chroma),
+            lightness: other.lightness.unwrap_or(self.lightness),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::default::UI_CONFIGURATION;
+
+    fn scale(steps: u8) -> Scale {
+        Scale {
+            steps,
+            ..UI_CONFIGURATION.theme.scale.clone()
+        }
+    }
+
+    #[test]
+    fn partial_configuration_fills_in_the_defaults() {
+        let partial: PartialUI =
+            serde_yaml::from_str("theme:\n  dark:\n    lightness: 0.6\n").unwrap();
+        let ui = UI_CONFIGURATION.clone().merge(partial);
+
+        // Everything but the lightness of the dark palette keeps its default
+        let mut expected = serde_json::to_value(&*UI_CONFIGURATION).unwrap();
+        expected["theme"]["dark"]["lightness"] = serde_json::json!(0.6);
+        assert_eq!(serde_json::to_value(&ui).unwrap(), expected);
+    }
+
+    #[test]
+    fn default_scale_has_every_hundred() {
+        assert_eq!(
+            scale(11).shades(),
+            [50, 100, 200, 300, 400, 500, 600, 700, 800, 900, 950]
+        );
+    }
+
+    #[test]
+    fn short_scales_keep_both_ends() {
+        assert!(scale(0).shades().is_empty());
+        assert_eq!(scale(2).shades(), [50, 950]);
+        assert_eq!(scale(3).shades(), [50, 500, 950]);
+        assert_eq!(scale(5).shades(), [50, 100, 500, 900, 950]);
+    }
+
+    #[test]
+    fn shades_are_unique_and_ordered() {
+        for steps in 2..=11 {
+            let shades = scale(steps).shades();
+            assert_eq!(shades.len(), usize::from(steps), "{} steps", steps);
+            assert!(
+                shades.windows(2).all(|pair| pair[0] < pair[1]),
+                "{:?}",
+                shades
+            );
+        }
+    }
+
+    #[test]
+    fn scale_curve_spans_start_to_end() {
+        let curve = &UI_CONFIGURATION.theme.scale.light;
+        let (chroma, lightness) = curve.tone(50, 0.2);
+        assert_eq!(lightness, curve.start.as_f64().unwrap());
+        assert!(chroma < 0.2);
+        let (_, lightness) = curve.tone(950, 0.2);
+        assert!((lightness - curve.end.as_f64().unwrap()).abs() < 1e-12);
+        // The chroma is kept in the middle of the scale
+        assert_eq!(curve.tone(500, 0.2).0, 0.2);
+    }
+
+    #[test]
+    fn neutral_hue_is_kept_replaced_or_cleared() {
+        let tinted = Neutral {
+            hue: Some(Number::from(250)),
+            ..UI_CONFIGURATION.theme.neutral.clone()
+        };
+        let merge = |patch: &str| {
+            let patch: PartialNeutral = serde_json::from_str(patch).unwrap();
+            tinted.clone().merge(patch).hue
+        };
+
+        assert_eq!(merge("{}"), Some(Number::from(250)));
+        assert_eq!(merge(r#"{ "hue": 120 }"#), Some(Number::from(120)));
+        assert_eq!(merge(r#"{ "hue": null }"#), None);
+
+        // The null survives serializing, e.g. in a resolved layer
+        let cleared: PartialNeutral = serde_json::from_str(r#"{ "hue": null }"#).unwrap();
+        assert_eq!(serde_json::to_string(&cleared).unwrap(), r#"{"hue":null}"#);
+        assert_eq!(tinted.clone().merge(cleared).tint(), (0.0, 0.0));
+    }
+}
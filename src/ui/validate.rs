@@ -0,0 +1,661 @@
+//! Validation of the theme of a loaded configuration.
+//!
+//! Unlike the checks of single files in [`crate::config::validate`], these checks look at the
+//! theme merged from every file, e.g. for colors whose variables clash with each other.
+
+use crate::{
+    config::{
+        types::{ConfigLayer, LoadedConfig},
+        validate::{Diagnostic, Severity},
+    },
+    ui::{
+        color::{Gamut, Oklch, contrast_ratio},
+        compile::{color_variable_names, semantic_variable_names},
+        types::{Mode, PartialPalette, PartialTheme, Theme, ThemeColor},
+    },
+};
+
+/// Checks the theme of a loaded configuration.
+/// Warnings are added to the layers they are about.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+///
+/// # Returns
+/// `Vec<Diagnostic>` - An error for every theme or semantic color whose variables clash, and
+/// for every semantic color that does not stand for a theme color.
+pub fn check_theme(loaded: &mut LoadedConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = check_theme_colors(loaded);
+    diagnostics.extend(check_semantic_colors(loaded));
+    if !diagnostics.is_empty() {
+        return diagnostics;
+    }
+
+    // Warn about configured colors that no display can show.
+    check_gamut(loaded);
+    check_on_colors(loaded);
+    Vec::new()
+}
+
+/// Warns about configured colors that sRGB displays cannot show.
+/// Such colors are mapped to the closest sRGB color for the hex fallback, and to the closest
+/// Display P3 color on wide-gamut displays, lowering their chroma. The default chroma relies on
+/// this to get the most colorful color of every hue, so only values set by the configuration
+/// are reported, each warning going to the layer that sets the value.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+fn check_gamut(loaded: &mut LoadedConfig) {
+    let Some(theme) = loaded.config.ui.as_ref().map(|ui| &ui.theme) else {
+        return;
+    };
+    // The last layer whose theme matches, as it sets the value in effect
+    let last_layer = |matches: &dyn Fn(&PartialTheme) -> bool| {
+        loaded
+            .layers
+            .iter()
+            .rposition(|layer| layer_theme(layer).is_some_and(matches))
+    };
+
+    let mut warnings = Vec::new();
+    for (mode, palette) in [(Mode::Light, &theme.light), (Mode::Dark, &theme.dark)] {
+        let mode_name = match mode {
+            Mode::Dark => "dark",
+            _ => "light",
+        };
+        for (name, color) in [("background", &palette.background), ("text", &palette.text)] {
+            let color = Oklch::from_numbers(&color.lightness, &color.chroma, &color.hue);
+            let is_set = |theme: &PartialTheme| {
+                mode_palette(theme, mode).is_some_and(|palette| match name {
+                    "background" => palette.background.is_some(),
+                    _ => palette.text.is_some(),
+                })
+            };
+            if let Some(layer) = last_layer(&is_set)
+                && !color.in_gamut(Gamut::Srgb)
+            {
+                warnings.push((
+                    layer,
+                    format!("ui.theme.{}.{}", mode_name, name),
+                    out_of_gamut_message(&format!("the {} {}", mode_name, name), color),
+                ));
+            }
+        }
+
+        // Colors using the chroma and lightness of the palette are reported together
+        let mut palette_colors = Vec::new();
+        for (name, theme_color) in &theme.colors {
+            let (chroma, lightness) = theme_color.tone(mode, palette);
+            let color = Oklch::from_numbers(lightness, chroma, theme_color.hue());
+            if color.in_gamut(Gamut::Srgb) {
+                continue;
+            }
+
+            let has_tone = |theme: &PartialTheme| {
+                let entry = theme
+                    .colors
+                    .as_ref()
+                    .and_then(|colors| colors.entries.get(name));
+                match (entry, mode) {
+                    (Some(Some(ThemeColor::Custom(color))), Mode::Dark) => color.dark.is_some(),
+                    (Some(Some(ThemeColor::Custom(color))), _) => color.light.is_some(),
+                    _ => false,
+                }
+            };
+            match last_layer(&has_tone) {
+                Some(layer) => warnings.push((
+                    layer,
+                    format!("ui.theme.colors.{}.{}", name, mode_name),
+                    out_of_gamut_message(&format!("the {} tone of {}", mode_name, name), color),
+                )),
+                None => palette_colors.push((name.as_str(), color)),
+            }
+        }
+
+        let sets_tone = |theme: &PartialTheme| {
+            mode_palette(theme, mode)
+                .is_some_and(|palette| palette.chroma.is_some() || palette.lightness.is_some())
+        };
+        if !palette_colors.is_empty()
+            && let Some(layer) = last_layer(&sets_tone)
+        {
+            let field = match layer_theme(&loaded.layers[layer])
+                .and_then(|theme| mode_palette(theme, mode))
+            {
+                Some(palette) if palette.chroma.is_some() => "chroma",
+                _ => "lightness",
+            };
+            // Name the widest gamut any of the colors is outside of
+            let gamut = if palette_colors
+                .iter()
+                .all(|(_, color)| color.in_gamut(Gamut::DisplayP3))
+            {
+                Gamut::Srgb
+            } else {
+                Gamut::DisplayP3
+            };
+            let palette_colors: Vec<&str> = palette_colors.iter().map(|(name, _)| *name).collect();
+            let names = match palette_colors.len() {
+                count if count > 3 => {
+                    format!("{} and {} more", palette_colors[..3].join(", "), count - 3)
+                }
+                _ => palette_colors.join(", "),
+            };
+            warnings.push((
+                layer,
+                format!("ui.theme.{}.{}", mode_name, field),
+                format!(
+                    "with chroma {} and lightness {}, the {} colors {} are outside the {} gamut; \
+                    they are shown with less chroma",
+                    palette.chroma,
+                    palette.lightness,
+                    mode_name,
+                    names,
+                    gamut.name()
+                ),
+            ));
+        }
+    }
+
+    for (layer, path, message) in warnings {
+        let layer = &mut loaded.layers[layer];
+        let diagnostic = Diagnostic::new(Severity::Warning, message).in_layer(&layer.source, &path);
+        layer.diagnostics.push(diagnostic);
+    }
+}
+
+/// Checks that the variables of every theme color, e.g. `--brand` and `--brand-on`, are not
+/// declared by the theme for something else: `--text`, the `--neutral-*` and `--surface-*`
+/// families, or another theme color such as `brand-on`.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+///
+/// # Returns
+/// `Vec<Diagnostic>` - An error for every theme color whose variables clash.
+fn check_theme_colors(loaded: &LoadedConfig) -> Vec<Diagnostic> {
+    let Some(theme) = loaded.config.ui.as_ref().map(|ui| &ui.theme) else {
+        return Vec::new();
+    };
+
+    // Theme colors declare the same variables as semantic colors of the same name
+    let variables: Vec<Vec<String>> = theme
+        .colors
+        .keys()
+        .map(|name| semantic_variable_names(theme, name))
+        .collect();
+    // The variables the theme declares without any color, e.g. `--text` and `--neutral-100`
+    let fixed = color_variable_names(&Theme {
+        colors: Default::default(),
+        ..theme.clone()
+    });
+
+    let mut diagnostics = Vec::new();
+    for (index, name) in theme.colors.keys().enumerate() {
+        // The variables must not redeclare the fixed variables of the theme, nor take the stem
+        // of a family of them, e.g. `surface` of `--surface-1`, nor redeclare those of an
+        // earlier theme color
+        let stem = format!("{}-", name);
+        let fixed_variable = fixed
+            .iter()
+            .find(|variable| variables[index].contains(variable) || variable.starts_with(&stem));
+        let other_color =
+            theme
+                .colors
+                .keys()
+                .take(index)
+                .enumerate()
+                .find_map(|(other_index, other)| {
+                    let variable = variables[index]
+                        .iter()
+                        .find(|variable| variables[other_index].contains(variable))?;
+                    Some((other, variable))
+                });
+        let message = if let Some(variable) = fixed_variable {
+            let declared_by = if ["neutral-", "surface-"]
+                .iter()
+                .any(|family| variable.starts_with(family))
+            {
+                "ui.theme.neutral generates"
+            } else {
+                "the theme already declares"
+            };
+            format!(
+                "color name {:?} clashes with --{}, which {}; choose another name",
+                name, variable, declared_by
+            )
+        } else if let Some((other, variable)) = other_color {
+            format!(
+                "colors {:?} and {:?} would both declare --{}; choose another name",
+                name, other, variable
+            )
+        } else {
+            continue;
+        };
+
+        // Point at the last layer setting the name
+        let layer = loaded.layers.iter().rev().find(|layer| {
+            layer_theme(layer)
+                .and_then(|theme| theme.colors.as_ref())
+                .is_some_and(|colors| colors.entries.get(name).is_some_and(Option::is_some))
+        });
+        let diagnostic = Diagnostic::new(Severity::Error, message);
+        diagnostics.push(match layer {
+            Some(layer) => diagnostic.in_layer(&layer.source, &format!("ui.theme.colors.{}", name)),
+            None => diagnostic.in_file(&loaded.path),
+        });
+    }
+    diagnostics
+}
+
+/// Checks that every semantic color stands for a theme color in each mode, directly or through
+/// other semantic colors, without going in circles, and that its aliases do not redeclare the
+/// variables of the theme, e.g. `--text` or `--neutral-100`, or of another semantic color.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+///
+/// # Returns
+/// `Vec<Diagnostic>` - An error for every semantic color that does not resolve.
+fn check_semantic_colors(loaded: &LoadedConfig) -> Vec<Diagnostic> {
+    let Some(theme) = loaded.config.ui.as_ref().map(|ui| &ui.theme) else {
+        return Vec::new();
+    };
+
+    let generated = color_variable_names(theme);
+    let mut diagnostics = Vec::new();
+    for name in theme.semantic.keys() {
+        let mut messages = Vec::new();
+        // The aliases must not redeclare the variables of the theme or of an earlier semantic
+        // color, nor take the stem of a family of theme variables, e.g. `surface` of `--surface-1`
+        let variables = semantic_variable_names(theme, name);
+        let stem = format!("{}-", name);
+        let theme_variable = generated
+            .iter()
+            .find(|variable| variables.contains(variable) || variable.starts_with(&stem));
+        let other_semantic = theme
+            .semantic
+            .keys()
+            .take_while(|other| *other != name)
+            .find_map(|other| {
+                let others = semantic_variable_names(theme, other);
+                let variable = variables
+                    .iter()
+                    .find(|variable| others.contains(variable))?;
+                Some((other, variable))
+            });
+        if theme.colors.contains_key(name) {
+            messages.push(format!(
+                "semantic color {:?} has the name of a theme color; choose another name",
+                name
+            ));
+        } else if let Some(variable) = theme_variable {
+            messages.push(format!(
+                "semantic color {:?} clashes with --{}, which the theme already declares; \
+                choose another name",
+                name, variable
+            ));
+        } else if let Some((other, variable)) = other_semantic {
+            messages.push(format!(
+                "semantic colors {:?} and {:?} would both declare --{}; choose another name",
+                name, other, variable
+            ));
+        }
+        for mode in [Mode::Light, Mode::Dark] {
+            if let Err(message) = resolve_semantic_color(theme, name, mode)
+                && !messages.contains(&message)
+            {
+                messages.push(message);
+            }
+        }
+
+        // Point at the last layer setting the name
+        let layer = loaded.layers.iter().rev().find(|layer| {
+            layer_theme(layer)
+                .and_then(|theme| theme.semantic.as_ref())
+                .is_some_and(|semantic| semantic.entries.get(name).is_some_and(Option::is_some))
+        });
+        for message in messages {
+            let diagnostic = Diagnostic::new(Severity::Error, message);
+            diagnostics.push(match layer {
+                Some(layer) => {
+                    diagnostic.in_layer(&layer.source, &format!("ui.theme.semantic.{}", name))
+                }
+                None => diagnostic.in_file(&loaded.path),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Follows a semantic color through other semantic colors to the theme color it stands for.
+///
+/// # Arguments
+/// * `theme` - The theme holding the colors.
+/// * `name` - The name of the semantic color.
+/// * `mode` - The mode to resolve it in.
+///
+/// # Returns
+/// `Result<&str, String>` - The name of the theme color, or why it cannot be found.
+fn resolve_semantic_color<'a>(
+    theme: &'a Theme,
+    name: &'a str,
+    mode: Mode,
+) -> Result<&'a str, String> {
+    let mut chain = vec![name];
+    let mut current = name;
+    while let Some(semantic) = theme.semantic.get(current) {
+        current = semantic.target(mode);
+        if chain.contains(&current) {
+            chain.push(current);
+            return Err(format!(
+                "semantic colors refer to each other in a cycle: {}",
+                chain.join(" → ")
+            ));
+        }
+        chain.push(current);
+    }
+
+    if theme.colors.contains_key(current) {
+        Ok(current)
+    } else {
+        Err(format!(
+            "semantic color {:?} refers to {:?}, which is neither a theme color nor a semantic \
+            color",
+            chain[chain.len() - 2],
+            current
+        ))
+    }
+}
+
+/// Warns about theme colors whose on-color cannot meet the configured contrast on sRGB or
+/// Display P3 displays, as neither black nor white contrasts enough with them. Such on-colors
+/// get the most contrast possible.
+/// The default contrast can always be met, so the warnings go to the layer setting it.
+///
+/// # Arguments
+/// * `loaded` - The loaded configuration.
+fn check_on_colors(loaded: &mut LoadedConfig) {
+    let Some(theme) = loaded.config.ui.as_ref().map(|ui| &ui.theme) else {
+        return;
+    };
+    if !theme.on_colors.enabled() {
+        return;
+    }
+    let Some(layer) = loaded.layers.iter().rposition(|layer| {
+        layer_theme(layer)
+            .and_then(|theme| theme.on_colors.as_ref())
+            .is_some_and(|on_colors| on_colors.contrast.is_some())
+    }) else {
+        return;
+    };
+
+    let contrast = theme.on_colors.contrast.as_f64().unwrap_or_default();
+    let mut failing = Vec::new();
+    for (mode_name, mode, palette) in [
+        ("light", Mode::Light, &theme.light),
+        ("dark", Mode::Dark, &theme.dark),
+    ] {
+        for (name, theme_color) in &theme.colors {
+            let color = theme_color.color(mode, palette);
+            // Each gamut gets its own on-color, against the color shown on its displays
+            for gamut in [Gamut::Srgb, Gamut::DisplayP3] {
+                let foreground = theme.on_colors.foreground(color, gamut);
+                let ratio = contrast_ratio(foreground, color, gamut);
+                if ratio < contrast {
+                    failing.push(format!(
+                        "{} in {} mode on {} displays ({:.2}:1)",
+                        name,
+                        mode_name,
+                        gamut.name(),
+                        ratio
+                    ));
+                }
+            }
+        }
+    }
+
+    if !failing.is_empty() {
+        let message = format!(
+            "no on-color reaches a contrast of {}:1 on {}; the most contrast possible is used \
+            instead",
+            theme.on_colors.contrast,
+            failing.join(", ")
+        );
+        let layer = &mut loaded.layers[layer];
+        let diagnostic = Diagnostic::new(Severity::Warning, message)
+            .in_layer(&layer.source, "ui.theme.on_colors.contrast");
+        layer.diagnostics.push(diagnostic);
+    }
+}
+
+/// Returns the theme set by a layer.
+fn layer_theme(layer: &ConfigLayer) -> Option<&PartialTheme> {
+    layer.config.ui.as_ref().and_then(|ui| ui.theme.as_ref())
+}
+
+/// Returns the palette of a mode set by a theme.
+fn mode_palette(theme: &PartialTheme, mode: Mode) -> Option<&PartialPalette> {
+    match mode {
+        Mode::Dark => theme.dark.as_ref(),
+        _ => theme.light.as_ref(),
+    }
+}
+
+/// Describes a color outside the sRGB gamut and the colors shown instead.
+fn out_of_gamut_message(subject: &str, color: Oklch) -> String {
+    if color.in_gamut(Gamut::DisplayP3) {
+        return format!(
+            "{}, {}, is outside the {} gamut; sRGB displays show it as {}",
+            subject,
+            color.to_css(),
+            Gamut::Srgb.name(),
+            color.to_hex()
+        );
+    }
+    format!(
+        "{}, {}, is outside the {} gamut; it is shown as {}, or as {} on sRGB displays",
+        subject,
+        color.to_css(),
+        Gamut::DisplayP3.name(),
+        color.map_to_gamut(Gamut::DisplayP3).to_css(),
+        color.to_hex()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{types::LoadOptions, validate::ValidationError},
+        helpers::{fs::load_seyuna_user_config, temp::TempDir},
+    };
+
+    /// Loads a configuration file written to a temporary directory.
+    async fn load(directory: &TempDir, content: &str) -> Result<LoadedConfig, Vec<Diagnostic>> {
+        let options = LoadOptions {
+            config_path: Some(directory.write("seyuna.json", content)),
+            ..LoadOptions::default()
+        };
+        load_seyuna_user_config(&options)
+            .await
+            .map_err(|e| match e.downcast::<ValidationError>() {
+                Ok(error) => error.diagnostics,
+                Err(e) => panic!("{:#}", e),
+            })
+    }
+
+    /// Loads a configuration and returns the messages of its errors.
+    async fn error_messages(content: &str) -> Vec<String> {
+        let directory = TempDir::new();
+        match load(&directory, content).await {
+            Ok(_) => Vec::new(),
+            Err(diagnostics) => diagnostics
+                .into_iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .map(|diagnostic| diagnostic.message)
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn theme_colors_clashing_with_generated_variables_are_errors() {
+        for (name, variable) in [
+            ("text", "text"),
+            ("background", "background"),
+            ("chroma", "chroma"),
+            ("lightness", "lightness"),
+        ] {
+            let content = format!(
+                r#"{{ "ui": {{ "theme": {{ "colors": {{ "{}": 10 }} }} }} }}"#,
+                name
+            );
+            let messages = error_messages(&content).await;
+            assert_eq!(messages.len(), 1, "{}", name);
+            assert!(
+                messages[0].contains(&format!("clashes with --{},", variable)),
+                "{}",
+                messages[0]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn theme_colors_clashing_with_each_other_are_errors() {
+        let messages = error_messages(
+            r#"{ "ui": { "theme": {
+                "colors": { "$replace": true, "brand": 10, "brand-on": 20 },
+                "on_colors": { "contrast": 4.5 }
+            } } }"#,
+        )
+        .await;
+        assert_eq!(
+            messages,
+            [r#"colors "brand-on" and "brand" would both declare --brand-on; choose another name"#]
+        );
+
+        let messages = error_messages(
+            r#"{ "ui": { "theme": {
+                "colors": { "$replace": true, "brand": 10, "brand-100": 20 },
+                "scale": { "steps": 11 }
+            } } }"#,
+        )
+        .await;
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("would both declare --brand-100"));
+
+        // Only the variables that are generated can clash
+        let messages = error_messages(
+            r#"{ "ui": { "theme": { "colors": { "$replace": true, "brand": 10, "brand-on": 20, "brand-100": 30 } } } }"#,
+        )
+        .await;
+        assert!(messages.is_empty(), "{:?}", messages);
+
+        let messages = error_messages(
+            r#"{ "ui": { "theme": { "colors": { "$replace": true, "brand": 10, "brand-dark": 20 } } } }"#,
+        )
+        .await;
+        assert!(messages.is_empty(), "{:?}", messages);
+    }
+
+    #[tokio::test]
+    async fn theme_colors_named_after_neutral_families_are_errors() {
+        let messages = error_messages(
+            r#"{ "ui": { "theme": {
+                "colors": { "neutral": 10, "surface": 20 },
+                "scale": { "steps": 11 },
+                "neutral": { "surface_step": 0.05 }
+            } } }"#,
+        )
+        .await;
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains(r#""neutral" clashes with --neutral-"#));
+        assert!(
+            messages[1].contains(
+                r#""surface" clashes with --surface-1, which ui.theme.neutral generates"#
+            )
+        );
+    }
+
+    /// A configuration with the theme color `brand`, every generated variable and the given
+    /// semantic colors.
+    fn semantic_config(semantic: &str) -> String {
+        format!(
+            r#"{{ "ui": {{ "theme": {{
+                "colors": {{ "$replace": true, "brand": 10 }},
+                "scale": {{ "steps": 11 }},
+                "neutral": {{ "surface_step": 0.05 }},
+                "on_colors": {{ "contrast": 4.5 }},
+                "semantic": {}
+            }} }} }}"#,
+            semantic
+        )
+    }
+
+    #[tokio::test]
+    async fn semantic_colors_resolve_through_each_other() {
+        let directory = TempDir::new();
+        let content = semantic_config(
+            r#"{ "primary": "brand", "action": { "light": "primary", "dark": "brand" } }"#,
+        );
+        let loaded = load(&directory, &content).await.unwrap();
+        let theme = &loaded.config.ui.as_ref().unwrap().theme;
+
+        assert_eq!(
+            resolve_semantic_color(theme, "action", Mode::Light),
+            Ok("brand")
+        );
+        assert_eq!(
+            resolve_semantic_color(theme, "action", Mode::Dark),
+            Ok("brand")
+        );
+    }
+
+    #[tokio::test]
+    async fn semantic_color_cycles_are_errors() {
+        let messages = error_messages(&semantic_config(
+            r#"{ "a": "b", "b": { "light": "a", "dark": "brand" } }"#,
+        ))
+        .await;
+        assert_eq!(
+            messages,
+            [
+                "semantic colors refer to each other in a cycle: a → b → a",
+                "semantic colors refer to each other in a cycle: b → a → b",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn semantic_aliases_to_missing_colors_are_errors() {
+        let messages = error_messages(&semantic_config(
+            r#"{ "primary": "accent", "action": { "light": "brand", "dark": "primary" } }"#,
+        ))
+        .await;
+        let missing = r#"semantic color "primary" refers to "accent", which is neither a theme color nor a semantic color"#;
+        assert_eq!(messages, [missing, missing]);
+    }
+
+    #[tokio::test]
+    async fn semantic_colors_clashing_with_generated_variables_are_errors() {
+        for (name, variable) in [
+            ("text", "text"),
+            ("brand-on", "brand-on"),
+            ("surface", "surface-1"),
+        ] {
+            let content = semantic_config(&format!(r#"{{ "{}": "brand" }}"#, name));
+            let messages = error_messages(&content).await;
+            assert_eq!(
+                messages,
+                [format!(
+                    "semantic color {:?} clashes with --{}, which the theme already declares; \
+                    choose another name",
+                    name, variable
+                )]
+            );
+        }
+
+        let messages = error_messages(&semantic_config(r#"{ "brand": "brand" }"#)).await;
+        assert!(messages[0].contains("has the name of a theme color"));
+    }
+}
@@ -0,0 +1,442 @@
+//! Compiling every member of a workspace, concurrently, with one summary for all of them.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use indexmap::IndexMap;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+
+use crate::{
+    config::{
+        extends::load_config_chain,
+        profile::profile_layers,
+        types::{LoadOptions, LoadedConfig},
+        validate::display_path,
+        workspace::workspace_members,
+    },
+    helpers::{
+        cli::{CHECKMARK, CROSSMARK, error_text, spinner_done, spinner_failed, spinner_start},
+        fs::load_seyuna_user_config,
+    },
+};
+
+use super::compile::{print_diagnostics, write_stylesheet};
+
+/// The outcome of compiling one member: its configuration and stylesheet, or the error.
+type MemberOutcome = Result<(LoadedConfig, PathBuf)>;
+
+/// Compiles every member of a workspace and prints one summary, without stopping at the
+/// first member that fails. With `watch`, keeps recompiling the members whose configuration
+/// files change, and every member when the root configuration changes.
+///
+/// # Arguments
+/// * `root` - The root configuration, declaring the workspace.
+/// * `members` - The members' configuration files.
+/// * `watch` - Whether to keep watching for changes.
+///
+/// # Returns
+/// * `Result<()>` - An error when a member failed to compile, outside of watch mode.
+pub async fn compile_workspace(
+    root: &LoadedConfig,
+    members: Vec<PathBuf>,
+    watch: bool,
+) -> Result<()> {
+    check_profile(&root.options, &members).await?;
+    let outcomes = compile_members(&root.options, &members).await;
+    let failed = outcomes.values().filter(|outcome| outcome.is_err()).count();
+
+    if !watch {
+        return match failed {
+            0 => Ok(()),
+            _ => Err(anyhow!(
+                "{} of {} workspace members failed to compile",
+                failed,
+                outcomes.len()
+            )),
+        };
+    }
+
+    println!("Seyuna watcher active...");
+
+    // Create a channel for receiving file change events. It is unbounded, so the watcher
+    // never blocks on sending while it is asked to watch another file.
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // Initialize the file watcher with a callback that sends events to the channel
+    let mut watcher = RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        notify::Config::default(),
+    )?;
+    let mut watched = HashSet::new();
+
+    // Watch the root configuration and the files of every member
+    let mut root_files = layer_files(root);
+    watch_files(&mut watcher, &mut watched, &root_files)?;
+    let mut member_files = IndexMap::new();
+    for (member, outcome) in &outcomes {
+        let files = member_files_of(member, outcome);
+        watch_files(&mut watcher, &mut watched, &files)?;
+        member_files.insert(member.clone(), files);
+    }
+
+    // Track the last event time for debouncing
+    let mut last_event = Instant::now();
+
+    // Listen for file change events asynchronously
+    while let Some(res) = rx.recv().await {
+        match res {
+            Ok(event) => {
+                // Only handle Modify events, ignoring events within 100ms
+                if !matches!(event.kind, EventKind::Modify(_))
+                    || last_event.elapsed() <= Duration::from_millis(100)
+                {
+                    continue;
+                }
+
+                // Wait a bit to ensure the file write is finished
+                sleep(Duration::from_millis(500)).await;
+                let changed: HashSet<PathBuf> =
+                    event.paths.iter().map(|path| canonical(path)).collect();
+
+                let affected: Vec<PathBuf> = if root_files.iter().any(|file| changed.contains(file))
+                {
+                    // The root changed: reload it and recompile every member
+                    match reload_root(root).await {
+                        Ok((reloaded, members)) => {
+                            root_files = layer_files(&reloaded);
+                            watch_files(&mut watcher, &mut watched, &root_files)?;
+                            member_files.retain(|member, _| members.contains(member));
+                            members
+                        }
+                        Err(e) => {
+                            eprintln!("{}", error_text(&format!("{:#}", e)));
+                            last_event = Instant::now();
+                            continue;
+                        }
+                    }
+                } else {
+                    member_files
+                        .iter()
+                        .filter(|(_, files)| files.iter().any(|file| changed.contains(file)))
+                        .map(|(member, _)| member.clone())
+                        .collect()
+                };
+
+                // Recompile the affected members and watch any newly extended files
+                let outcomes = compile_members(&root.options, &affected).await;
+                for (member, outcome) in &outcomes {
+                    let files = member_files_of(member, outcome);
+                    watch_files(&mut watcher, &mut watched, &files)?;
+                    member_files.insert(member.clone(), files);
+                }
+                last_event = Instant::now();
+            }
+            // Print any errors from the watcher
+            Err(e) => println!("Watch error: {:?}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Fails if the selected profile is declared by no member, as neither the root nor any single
+/// member has to declare it. Members that fail to load are left to report their own errors.
+///
+/// # Arguments
+/// * `options` - How the root configuration was loaded.
+/// * `members` - The members' configuration files.
+///
+/// # Returns
+/// `Result<()>` - An error if no member declares the profile.
+async fn check_profile(options: &LoadOptions, members: &[PathBuf]) -> Result<()> {
+    let Some(profile) = &options.profile else {
+        return Ok(());
+    };
+    for member in members {
+        match load_config_chain(member).await {
+            Ok(layers) if profile_layers(&layers, profile, true).is_err() => {}
+            _ => return Ok(()),
+        }
+    }
+    Err(anyhow!(
+        "Unknown profile {:?}. No workspace member declares it.",
+        profile
+    ))
+}
+
+/// Compiles members concurrently, one task each, and prints a summary of the results.
+///
+/// The root's profile and `--set` overrides apply to every member. Members that do not declare
+/// the profile are compiled without it.
+///
+/// # Arguments
+/// * `options` - How the root configuration was loaded.
+/// * `members` - The members' configuration files.
+///
+/// # Returns
+/// `IndexMap<PathBuf, MemberOutcome>` - The outcome of every member, in declaration order.
+async fn compile_members(
+    options: &LoadOptions,
+    members: &[PathBuf],
+) -> IndexMap<PathBuf, MemberOutcome> {
+    if members.is_empty() {
+        return IndexMap::new();
+    }
+
+    // Start spinner
+    let spinner = spinner_start(&match &options.profile {
+        Some(profile) => format!(
+            "Compiling {} workspace members ({} profile)",
+            members.len(),
+            profile
+        ),
+        None => format!("Compiling {} workspace members", members.len()),
+    });
+
+    // Load every member first, one task each, to find the members sharing an output directory
+    let tasks: Vec<_> = members
+        .iter()
+        .map(|member| {
+            let options = LoadOptions {
+                config_path: Some(member.clone()),
+                workspace_member: true,
+                ..options.clone()
+            };
+            tokio::spawn(async move { load_seyuna_user_config(&options).await })
+        })
+        .collect();
+    let mut loads = Vec::new();
+    for task in tasks {
+        loads.push(
+            task.await
+                .unwrap_or_else(|e| Err(anyhow!("The compilation task failed: {}", e))),
+        );
+    }
+
+    // Members sharing an output directory would overwrite and clean up each other's files
+    let output_dirs: Vec<Option<PathBuf>> = loads
+        .iter()
+        .map(|loaded| {
+            let output_dir = loaded.as_ref().ok()?.output_dir().ok()?;
+            Some(normalize(&output_dir))
+        })
+        .collect();
+    let tasks: Vec<_> = loads
+        .into_iter()
+        .enumerate()
+        .map(|(index, loaded)| {
+            let shared = output_dirs[index].as_ref().and_then(|output_dir| {
+                let other = output_dirs
+                    .iter()
+                    .enumerate()
+                    .position(|(other, dir)| other != index && dir.as_ref() == Some(output_dir))?;
+                Some((output_dir.clone(), &members[other]))
+            });
+            let loaded = match shared {
+                Some((output_dir, other)) => Err(anyhow!(
+                    "output_dir {} is also the output directory of {}; give every workspace \
+                    member its own",
+                    display_path(&output_dir),
+                    display_path(other.parent().unwrap_or(other))
+                )),
+                None => loaded,
+            };
+            tokio::spawn(async move {
+                let loaded = loaded?;
+                let file_path = write_stylesheet(&loaded).await?;
+                Ok((loaded, file_path))
+            })
+        })
+        .collect();
+
+    let mut outcomes = IndexMap::new();
+    for (member, task) in members.iter().zip(tasks) {
+        let outcome = task
+            .await
+            .unwrap_or_else(|e| Err(anyhow!("The compilation task failed: {}", e)));
+        outcomes.insert(member.clone(), outcome);
+    }
+
+    // Display completed progress
+    let failed = outcomes.values().filter(|outcome| outcome.is_err()).count();
+    if failed == 0 {
+        spinner_done(
+            &spinner,
+            &format!(
+                "Successfully compiled {} workspace members!",
+                outcomes.len()
+            ),
+        );
+    } else {
+        spinner_failed(
+            &spinner,
+            &format!(
+                "{} of {} workspace members failed to compile",
+                failed,
+                outcomes.len()
+            ),
+        );
+    }
+
+    // Print the result of every member
+    for (member, outcome) in &outcomes {
+        let name = display_path(member.parent().unwrap_or(member));
+        match outcome {
+            Ok((loaded, file_path)) => {
+                println!("  {} {} → {}", *CHECKMARK, name, display_path(file_path));
+                print_diagnostics(loaded);
+            }
+            Err(e) => {
+                println!("  {} {}", *CROSSMARK, name);
+                eprintln!("{}\n", error_text(&format!("{:#}", e)));
+            }
+        }
+    }
+
+    outcomes
+}
+
+/// Reloads the root configuration and finds its members again.
+///
+/// # Arguments
+/// * `root` - The root configuration as first loaded.
+///
+/// # Returns
+/// `Result<(LoadedConfig, Vec<PathBuf>)>` - The reloaded root and its members.
+async fn reload_root(root: &LoadedConfig) -> Result<(LoadedConfig, Vec<PathBuf>)> {
+    let reloaded = load_seyuna_user_config(&LoadOptions {
+        config_path: Some(root.path.clone()),
+        ..root.options.clone()
+    })
+    .await?;
+    print_diagnostics(&reloaded);
+
+    let members = workspace_members(&reloaded)?
+        .ok_or_else(|| anyhow!("The root configuration no longer declares a workspace"))?;
+    Ok((reloaded, members))
+}
+
+/// Returns the files a configuration was loaded from.
+pub fn layer_files(loaded: &LoadedConfig) -> Vec<PathBuf> {
+    loaded
+        .layers
+        .iter()
+        .filter_map(|layer| layer.source.path())
+        .map(canonical)
+        .collect()
+}
+
+/// Returns the files to watch for a member: the files it was loaded from, or its
+/// configuration file when it failed to load.
+fn member_files_of(member: &Path, outcome: &MemberOutcome) -> Vec<PathBuf> {
+    match outcome {
+        Ok((loaded, _)) => layer_files(loaded),
+        Err(_) => vec![canonical(member)],
+    }
+}
+
+/// Watches the files that are not watched yet.
+pub fn watch_files(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    files: &[PathBuf],
+) -> Result<()> {
+    for file in files {
+        if watched.insert(file.clone()) {
+            watcher.watch(file, RecursiveMode::NonRecursive)?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the `.` and `..` components of a path without touching the file system, so paths
+/// to directories that do not exist yet can be compared.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Canonicalizes a path so the paths of events and configurations can be compared.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::temp::TempDir;
+
+    #[tokio::test]
+    async fn members_sharing_an_output_dir_are_refused() {
+        let directory = TempDir::new();
+        let members = vec![
+            directory.write(
+                "apps/a/seyuna.json",
+                r#"{ "ui": { "output_dir": "../../dist" } }"#,
+            ),
+            directory.write(
+                "apps/b/seyuna.json",
+                r#"{ "ui": { "output_dir": "../../dist" } }"#,
+            ),
+            directory.write(
+                "apps/c/seyuna.json",
+                r#"{ "ui": { "output_dir": "dist" } }"#,
+            ),
+        ];
+
+        let outcomes = compile_members(&LoadOptions::default(), &members).await;
+
+        let errors: Vec<String> = outcomes
+            .values()
+            .filter_map(|outcome| outcome.as_ref().err().map(|e| e.to_string()))
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("is also the output directory of"));
+        assert!(outcomes[&members[2]].is_ok());
+        assert!(!directory.path().join("dist").exists());
+    }
+
+    #[tokio::test]
+    async fn profile_only_has_to_be_declared_by_a_member() {
+        let directory = TempDir::new();
+        let root = directory.write(
+            "seyuna.json",
+            r#"{ "workspace": { "members": ["apps/*"] } }"#,
+        );
+        directory.write(
+            "apps/a/seyuna.json",
+            r#"{ "profiles": { "production": { "ui": { "output_dir": "build" } } } }"#,
+        );
+        directory.write("apps/b/seyuna.json", "{}");
+
+        for (profile, declared) in [("production", true), ("staging", false)] {
+            let options = LoadOptions {
+                config_path: Some(root.clone()),
+                profile: Some(profile.to_string()),
+                ..LoadOptions::default()
+            };
+            let loaded = load_seyuna_user_config(&options).await.unwrap();
+            let members = workspace_members(&loaded).unwrap().unwrap();
+
+            let result = compile_workspace(&loaded, members, false).await;
+            assert_eq!(result.is_ok(), declared, "{}", profile);
+        }
+        assert!(directory.path().join("apps/a/build").exists());
+        assert!(directory.path().join("apps/b/styles").exists());
+    }
+}